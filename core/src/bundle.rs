@@ -0,0 +1,343 @@
+//! Decks: word lists bundled with their images and license into a single file.
+
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{Read, Seek, Write};
+use std::path::{Component, Path};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use zip::write::FileOptions;
+use zip::{ZipArchive, ZipWriter};
+use crate::matching::MatchPolicy;
+use crate::source::ImageSource;
+use crate::Word;
+
+const MANIFEST: &str = "manifest.json";
+const LICENSE: &str = "LICENSE.txt";
+const VERSION: u32 = 1;
+
+/// Reasons a deck cannot be read or written.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Reading or writing the file failed.
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    /// The deck is not a valid zip archive.
+    #[error(transparent)]
+    ZipError(#[from] zip::result::ZipError),
+    /// The manifest is not valid.
+    #[error(transparent)]
+    JsonError(#[from] serde_json::Error),
+    /// The deck was written by a newer version.
+    #[error("unsupported deck version {0}")]
+    UnsupportedVersionError(u32),
+    /// The manifest lists an image that is not in the archive.
+    #[error("the image {0} is missing from the deck")]
+    MissingImageError(String),
+    /// The manifest lists an image outside of the archive.
+    #[error("the path {0} points outside of the deck")]
+    UnsafePathError(String),
+    /// An image file extension contains something else than letters and digits.
+    #[error("invalid image file extension {0:?}")]
+    ExtensionError(String),
+}
+
+/// The contents of `manifest.json`: what the deck is called and which words it holds.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// The version of the deck format.
+    pub version: u32,
+    /// The name of the deck.
+    pub name: String,
+    /// Under which terms the deck may be shared.
+    #[serde(default)]
+    pub license: String,
+    /// How strictly guesses are compared to the spellings of the words.
+    #[serde(default)]
+    pub policy: MatchPolicy,
+    /// The words of the deck in the order they were added.
+    pub words: Vec<Entry>,
+}
+
+/// A word of the deck.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    /// The canonical spelling.
+    pub word: String,
+    /// Further spellings that are accepted as correct.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alternatives: Vec<String>,
+    /// The path of the image inside the deck.
+    pub image: String,
+    /// Who made the picture.
+    #[serde(default)]
+    pub credits: String,
+}
+
+/// A single file deck: a zip archive containing a `manifest.json`, the images of all words and a `LICENSE.txt`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Bundle {
+    /// The name, license and words of the deck.
+    pub manifest: Manifest,
+    images: BTreeMap<String, Vec<u8>>,
+}
+
+impl Bundle {
+    /// Creates an empty deck.
+    pub fn new(name: impl Into<String>, license: impl Into<String>, policy: MatchPolicy) -> Self {
+        Self {
+            manifest: Manifest {
+                version: VERSION,
+                name: name.into(),
+                license: license.into(),
+                policy,
+                words: Vec::new(),
+            },
+            images: BTreeMap::new(),
+        }
+    }
+
+    /// Adds `word` with its image `data`. `extension` is used for the file name of the image inside the deck and may
+    /// only contain ASCII letters and digits.
+    pub fn add(&mut self, word: &Word, extension: &str, data: Vec<u8>) -> Result<(), Error> {
+        if !is_valid_extension(extension) {
+            return Err(Error::ExtensionError(extension.to_owned()));
+        }
+        let image = format!("images/{}.{extension}", self.manifest.words.len());
+        self.images.insert(image.clone(), data);
+        self.manifest.words.push(Entry {
+            word: word.word.clone(),
+            alternatives: word.alternatives.clone(),
+            image,
+            credits: word.credits.clone(),
+        });
+        Ok(())
+    }
+
+    /// Returns the image of `entry`, if the deck contains it.
+    pub fn image(&self, entry: &Entry) -> Option<&[u8]> {
+        self.images.get(&entry.image).map(Vec::as_slice)
+    }
+
+    /// Returns the SHA-256 hash of the manifest and the images as hex string. Decks with the same content have the same
+    /// digest, no matter how the archive was compressed.
+    pub fn digest(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(serde_json::to_vec(&self.manifest).expect("The manifest only contains strings and numbers"));
+        for (image, data) in &self.images {
+            hasher.update((image.len() as u64).to_le_bytes());
+            hasher.update(image);
+            hasher.update((data.len() as u64).to_le_bytes());
+            hasher.update(data);
+        }
+        hasher.finalize().iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    /// Reads the deck at `path`.
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        Self::read(File::open(path)?)
+    }
+
+    /// Writes the deck to `path`.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        self.write(File::create(path)?)
+    }
+
+    /// Reads a deck from `reader`, rejecting images outside of the archive.
+    pub fn read(reader: impl Read + Seek) -> Result<Self, Error> {
+        let mut archive = ZipArchive::new(reader)?;
+        let manifest: Manifest = serde_json::from_reader(archive.by_name(MANIFEST)?)?;
+        if manifest.version > VERSION {
+            return Err(Error::UnsupportedVersionError(manifest.version));
+        }
+
+        let mut images = BTreeMap::new();
+        for entry in &manifest.words {
+            if !is_enclosed(&entry.image) {
+                return Err(Error::UnsafePathError(entry.image.clone()));
+            }
+            let mut file = archive.by_name(&entry.image)
+                .map_err(|_| Error::MissingImageError(entry.image.clone()))?;
+            let mut data = Vec::new();
+            file.read_to_end(&mut data)?;
+            images.insert(entry.image.clone(), data);
+        }
+
+        Ok(Self {
+            manifest,
+            images,
+        })
+    }
+
+    /// Writes the deck to `writer` together with a license file crediting every image.
+    pub fn write(&self, writer: impl Write + Seek) -> Result<(), Error> {
+        let mut archive = ZipWriter::new(writer);
+        let options = FileOptions::default();
+
+        archive.start_file(MANIFEST, options)?;
+        serde_json::to_writer_pretty(&mut archive, &self.manifest)?;
+
+        archive.start_file(LICENSE, options)?;
+        writeln!(archive, "{}", self.manifest.license)?;
+        writeln!(archive)?;
+        writeln!(archive, "Images:")?;
+        for entry in &self.manifest.words {
+            writeln!(archive, "{}: {}", entry.image, entry.credits)?;
+        }
+
+        for (image, data) in &self.images {
+            archive.start_file(image, options)?;
+            archive.write_all(data)?;
+        }
+        archive.finish()?;
+        Ok(())
+    }
+
+    /// Writes the images into `dir` and returns the words pointing at them.
+    pub fn unpack(&self, dir: &Path) -> Result<Vec<Word>, Error> {
+        std::fs::create_dir_all(dir)?;
+        let dir = dir.canonicalize()?;
+
+        self.manifest.words.iter()
+            .map(|entry| {
+                let data = self.image(entry).ok_or_else(|| Error::MissingImageError(entry.image.clone()))?;
+                let path = dir.join(&entry.image);
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&path, data)?;
+
+                Ok(Word {
+                    word: entry.word.clone(),
+                    alternatives: entry.alternatives.clone(),
+                    url: ImageSource::File(path),
+                    credits: entry.credits.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Returns whether `extension` can be used for the file name of an image inside a deck.
+pub fn is_valid_extension(extension: &str) -> bool {
+    !extension.is_empty() && extension.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+fn is_enclosed(path: &str) -> bool {
+    Path::new(path).components().all(|component| matches!(component, Component::Normal(_)))
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+    use std::str::FromStr;
+    use super::*;
+
+    fn word(spelling: &str) -> Word {
+        Word {
+            word: spelling.to_owned(),
+            alternatives: Vec::new(),
+            url: ImageSource::from_str("https://example.org/image.png").expect("valid url"),
+            credits: format!("{spelling} by someone"),
+        }
+    }
+
+    fn bundle() -> Bundle {
+        let mut bundle = Bundle::new("Animals", "CC-BY 4.0", MatchPolicy::default());
+        bundle.add(&word("dog"), "png", vec![1, 2, 3]).expect("valid extension");
+        bundle.add(&word("cat"), "jpg", vec![4, 5]).expect("valid extension");
+        bundle
+    }
+
+    fn write(bundle: &Bundle) -> Vec<u8> {
+        let mut data = Cursor::new(Vec::new());
+        bundle.write(&mut data).expect("writable bundle");
+        data.into_inner()
+    }
+
+    #[test]
+    fn round_trip() {
+        let bundle = bundle();
+
+        let restored = Bundle::read(Cursor::new(write(&bundle))).expect("readable bundle");
+
+        assert_eq!(restored, bundle, "expect the same bundle after reading it back");
+        assert_eq!(restored.image(&restored.manifest.words[1]), Some(&[4, 5][..]), "expect the image data to be restored");
+    }
+
+    #[test]
+    fn contains_license() {
+        let mut archive = ZipArchive::new(Cursor::new(write(&bundle()))).expect("valid zip");
+        let mut license = String::new();
+        archive.by_name(LICENSE).expect("license file").read_to_string(&mut license).expect("utf-8 license");
+
+        assert!(license.starts_with("CC-BY 4.0"), "expect the deck license");
+        assert!(license.contains("images/0.png: dog by someone"), "expect the image credits");
+    }
+
+    #[test]
+    fn invalid_extension() {
+        let mut bundle = bundle();
+
+        for extension in ["", "../png", "svg+xml", "png/"] {
+            assert!(matches!(bundle.add(&word("pig"), extension, Vec::new()), Err(Error::ExtensionError(_))), "expect {extension:?} to be rejected");
+        }
+        assert_eq!(bundle.manifest.words.len(), 2, "expect no word to be added");
+    }
+
+    #[test]
+    fn digest() {
+        let restored = Bundle::read(Cursor::new(write(&bundle()))).expect("readable bundle");
+        let mut changed = bundle();
+        changed.images.insert("images/0.png".to_owned(), vec![1, 2, 4]);
+
+        assert_eq!(restored.digest(), bundle().digest(), "expect the same content to have the same digest");
+        assert_ne!(changed.digest(), bundle().digest(), "expect different images to change the digest");
+        assert_eq!(bundle().digest().len(), 64, "expect a hex encoded SHA-256 hash");
+    }
+
+    #[test]
+    fn missing_image() {
+        let mut bundle = bundle();
+        bundle.images.remove("images/0.png");
+
+        let error = Bundle::read(Cursor::new(write(&bundle))).unwrap_err();
+
+        assert!(matches!(error, Error::MissingImageError(image) if image == "images/0.png"), "expect the missing image to be reported");
+    }
+
+    #[test]
+    fn unsafe_image_path() {
+        let mut bundle = bundle();
+        bundle.manifest.words[0].image = "../outside.png".to_owned();
+
+        let error = Bundle::read(Cursor::new(write(&bundle))).unwrap_err();
+
+        assert!(matches!(error, Error::UnsafePathError(_)), "expect a path outside of the deck to be rejected");
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let mut bundle = bundle();
+        bundle.manifest.version = VERSION + 1;
+
+        let error = Bundle::read(Cursor::new(write(&bundle))).unwrap_err();
+
+        assert!(matches!(error, Error::UnsupportedVersionError(_)), "expect a newer deck to be rejected");
+    }
+
+    #[test]
+    fn unpack_images() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+
+        let words = bundle().unpack(dir.path()).expect("unpackable bundle");
+
+        assert_eq!(words.len(), 2, "expect a word for every entry");
+        assert_eq!(words[0].word, "dog", "expect the spelling to be kept");
+        assert_eq!(words[0].credits, "dog by someone", "expect the credits to be kept");
+        let ImageSource::File(path) = &words[0].url else { panic!("expect a file source") };
+        assert_eq!(std::fs::read(path).expect("unpacked image"), vec![1, 2, 3], "expect the image to be written to the directory");
+    }
+}
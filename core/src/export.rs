@@ -0,0 +1,137 @@
+//! Writes word lists to CSV, JSON and TOML files.
+
+use std::fmt::Debug;
+use std::path::Path;
+use serde::Serialize;
+use thiserror::Error;
+use crate::import::Format;
+use crate::Word;
+
+/// Reasons a word list cannot be exported.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Writing the file failed.
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    /// The file extension is not one of the supported formats.
+    #[error("unsupported file type, expected a .csv, .json or .toml file")]
+    UnsupportedFormatError,
+    /// The words cannot be written as CSV.
+    #[error(transparent)]
+    CsvError(#[from] csv::Error),
+    /// The words cannot be written as JSON.
+    #[error(transparent)]
+    JsonError(#[from] serde_json::Error),
+    /// The words cannot be written as TOML.
+    #[error(transparent)]
+    TomlError(#[from] toml::ser::Error),
+}
+
+/// CSV has no lists, so alternative spellings are joined with `|` into a single column.
+#[derive(Serialize)]
+struct CsvRow<'a> {
+    word: &'a str,
+    url: String,
+    credits: &'a str,
+    alternatives: String,
+}
+
+#[derive(Serialize)]
+struct TomlFile<'a> {
+    words: &'a [Word],
+}
+
+/// Writes `words` to `path` in the format given by its extension.
+pub fn export(words: &[Word], path: &Path) -> Result<(), Error> {
+    let format = Format::from_path(path).ok_or(Error::UnsupportedFormatError)?;
+    std::fs::write(path, serialize(words, format)?)?;
+    Ok(())
+}
+
+/// Returns `words` written in `format`.
+pub fn serialize(words: &[Word], format: Format) -> Result<String, Error> {
+    match format {
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for word in words {
+                writer.serialize(CsvRow {
+                    word: &word.word,
+                    url: word.url.to_string(),
+                    credits: &word.credits,
+                    alternatives: word.alternatives.join("|"),
+                })?;
+            }
+            let csv = writer.into_inner().map_err(|error| error.into_error())?;
+            Ok(String::from_utf8(csv).expect("csv writer produced invalid utf-8"))
+        }
+        Format::Json => Ok(serde_json::to_string_pretty(words)?),
+        Format::Toml => Ok(toml::to_string(&TomlFile { words })?),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+    use crate::{import, Trainer};
+    use crate::source::ImageSource;
+    use super::*;
+
+    fn words() -> Vec<Word> {
+        vec![
+            Word {
+                word: "colour".to_owned(),
+                alternatives: vec!["color".to_owned()],
+                url: ImageSource::from_str("https://colour.org/").expect("valid url"),
+                credits: "Colour, \"quoted\"".to_owned(),
+            },
+            Word {
+                word: "dog".to_owned(),
+                alternatives: Vec::new(),
+                url: ImageSource::from_str("https://dog.org/?a=1&b=2").expect("valid url"),
+                credits: String::new(),
+            },
+        ]
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let csv = serialize(&words(), Format::Csv).expect("serializable words");
+
+        assert!(csv.starts_with("word,url,credits,alternatives\n"), "expect a header row");
+        assert_eq!(import::parse(Format::Csv, &csv).expect("importable words").words, words(), "expect the same words after importing");
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let json = serialize(&words(), Format::Json).expect("serializable words");
+
+        assert_eq!(import::parse(Format::Json, &json).expect("importable words").words, words(), "expect the same words after importing");
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        let toml = serialize(&words(), Format::Toml).expect("serializable words");
+
+        assert_eq!(import::parse(Format::Toml, &toml).expect("importable words").words, words(), "expect the same words after importing");
+    }
+
+    #[test]
+    fn export_keeps_relative_images() {
+        let from = tempfile::tempdir().expect("temporary directory");
+        let to = tempfile::tempdir().expect("temporary directory");
+        std::fs::write(from.path().join("words.csv"), "word,url\ndog,images/dog.png\n").expect("writable word list");
+        let mut trainer = Trainer::new(import::import(&from.path().join("words.csv")).expect("importable words").words);
+        trainer.set_directory(Some(from.path().to_owned()));
+
+        export(&trainer.resolved_words(), &to.path().join("words.csv")).expect("exported words");
+        let words = import::import(&to.path().join("words.csv")).expect("importable words").words;
+
+        assert_eq!(words[0].url, ImageSource::File(from.path().join("images/dog.png")), "expect the image to point at the original folder");
+        assert_eq!(words[0].url.resolve(Some(to.path())), words[0].url, "expect the image not to depend on the new folder");
+    }
+
+    #[test]
+    fn export_unsupported_format() {
+        assert!(matches!(export(&words(), Path::new("words.txt")), Err(Error::UnsupportedFormatError)), "expect an unknown extension to be rejected");
+    }
+}
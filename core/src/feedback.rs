@@ -0,0 +1,217 @@
+//! Checks guesses and tells the learner what was wrong about them.
+
+use std::fmt::Debug;
+use crate::matching::MatchPolicy;
+use crate::Word;
+
+/// A single step of the per-letter difference between the expected spelling and a guess.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Letter {
+    /// The letter was typed as expected.
+    Same(char),
+    /// The letter is expected but was not typed.
+    Missing(char),
+    /// The letter was typed but is not expected.
+    Extra(char),
+    /// Another letter was typed instead of the expected one.
+    Replaced {
+        /// The letter of the spelling.
+        expected: char,
+        /// The letter of the guess.
+        guessed: char,
+    },
+    /// Two neighbouring letters were typed in the wrong order. They are given in the expected order.
+    Swapped(char, char),
+}
+
+/// The result of checking a guess.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GuessOutcome {
+    /// The guess matched a spelling of the word.
+    Correct,
+    /// The guess was only a few letters off the closest spelling.
+    NearMiss {
+        /// How many letters are missing, extra, replaced or swapped.
+        distance: usize,
+        /// The difference to the closest spelling, letter by letter.
+        diff: Vec<Letter>,
+    },
+    /// The guess was not close to any spelling.
+    Wrong,
+}
+
+impl GuessOutcome {
+    /// Returns whether the guess was correct.
+    pub fn is_correct(&self) -> bool {
+        matches!(self, GuessOutcome::Correct)
+    }
+
+    /// Returns whether the guess was a near miss.
+    pub fn is_near_miss(&self) -> bool {
+        matches!(self, GuessOutcome::NearMiss { .. })
+    }
+}
+
+/// Checks `guess` against the spellings of `word` and compares it to the closest one if it is wrong.
+pub fn evaluate(word: &Word, guess: &str, policy: &MatchPolicy) -> GuessOutcome {
+    if word.spellings().any(|spelling| policy.matches(spelling, guess)) {
+        return GuessOutcome::Correct;
+    }
+
+    let guess = policy.normalize(guess);
+    word.spellings()
+        .map(|spelling| {
+            let spelling = policy.normalize(spelling);
+            let (distance, diff) = diff(&spelling, &guess);
+            (spelling.chars().count(), distance, diff)
+        })
+        .min_by_key(|(_, distance, _)| *distance)
+        .filter(|(length, distance, _)| *distance <= near_miss_distance(*length))
+        .map_or(GuessOutcome::Wrong, |(_, distance, diff)| GuessOutcome::NearMiss { distance, diff })
+}
+
+/// The number of edits a guess may be away from a word of `length` letters to count as a near-miss.
+fn near_miss_distance(length: usize) -> usize {
+    (length / 4).max(1)
+}
+
+/// Computes the optimal string alignment distance (Damerau-Levenshtein without repeated edits of the
+/// same substring) between `expected` and `guess` and the letters that lead to it.
+pub fn diff(expected: &str, guess: &str) -> (usize, Vec<Letter>) {
+    let expected = expected.chars().collect::<Vec<_>>();
+    let guess = guess.chars().collect::<Vec<_>>();
+    let (n, m) = (expected.len(), guess.len());
+
+    let mut distances = vec![vec![0; m + 1]; n + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=n {
+        for j in 1..=m {
+            let cost = usize::from(expected[i - 1] != guess[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && expected[i - 1] == guess[j - 2] && expected[i - 2] == guess[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+
+    let mut letters = Vec::new();
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        let distance = distances[i][j];
+        if i > 0 && j > 0 && expected[i - 1] == guess[j - 1] && distance == distances[i - 1][j - 1] {
+            letters.push(Letter::Same(expected[i - 1]));
+            i -= 1;
+            j -= 1;
+        } else if i > 1 && j > 1 && expected[i - 1] == guess[j - 2] && expected[i - 2] == guess[j - 1]
+            && distance == distances[i - 2][j - 2] + 1 {
+            letters.push(Letter::Swapped(expected[i - 2], expected[i - 1]));
+            i -= 2;
+            j -= 2;
+        } else if i > 0 && j > 0 && distance == distances[i - 1][j - 1] + 1 {
+            letters.push(Letter::Replaced { expected: expected[i - 1], guessed: guess[j - 1] });
+            i -= 1;
+            j -= 1;
+        } else if i > 0 && distance == distances[i - 1][j] + 1 {
+            letters.push(Letter::Missing(expected[i - 1]));
+            i -= 1;
+        } else {
+            letters.push(Letter::Extra(guess[j - 1]));
+            j -= 1;
+        }
+    }
+    letters.reverse();
+
+    (distances[n][m], letters)
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+    use crate::source::ImageSource;
+    use super::*;
+
+    fn word(spelling: &str) -> Word {
+        Word {
+            word: spelling.to_owned(),
+            alternatives: Vec::new(),
+            url: ImageSource::from_str("https://example.org/").expect("valid url"),
+            credits: String::new(),
+        }
+    }
+
+    #[test]
+    fn diff_same() {
+        let (distance, letters) = diff("cat", "cat");
+
+        assert_eq!(distance, 0, "expect no edits");
+        assert_eq!(letters, vec![Letter::Same('c'), Letter::Same('a'), Letter::Same('t')], "expect only equal letters");
+    }
+
+    #[test]
+    fn diff_missing_letter() {
+        let (distance, letters) = diff("apple", "aple");
+
+        assert_eq!(distance, 1, "expect one edit");
+        assert_eq!(letters.iter().filter(|letter| matches!(letter, Letter::Missing('p'))).count(), 1, "expect the missing p");
+    }
+
+    #[test]
+    fn diff_extra_letter() {
+        let (distance, letters) = diff("dog", "doog");
+
+        assert_eq!(distance, 1, "expect one edit");
+        assert_eq!(letters.iter().filter(|letter| matches!(letter, Letter::Extra('o'))).count(), 1, "expect the extra o");
+    }
+
+    #[test]
+    fn diff_replaced_letter() {
+        let (distance, letters) = diff("cat", "cut");
+
+        assert_eq!(distance, 1, "expect one edit");
+        assert_eq!(letters[1], Letter::Replaced { expected: 'a', guessed: 'u' }, "expect the replaced vowel");
+    }
+
+    #[test]
+    fn diff_swapped_letters() {
+        let (distance, letters) = diff("raspberry", "rapsberry");
+
+        assert_eq!(distance, 1, "expect a swap to count as one edit");
+        assert_eq!(letters[2], Letter::Swapped('s', 'p'), "expect the swapped letters in the expected order");
+        assert_eq!(letters.len(), 8, "expect the swap to cover two letters");
+    }
+
+    #[test]
+    fn evaluate_correct() {
+        assert_eq!(evaluate(&word("apple"), "apple", &MatchPolicy::default()), GuessOutcome::Correct, "expect the guess to be correct");
+    }
+
+    #[test]
+    fn evaluate_near_miss() {
+        let outcome = evaluate(&word("apple"), "appel", &MatchPolicy::default());
+
+        assert!(matches!(outcome, GuessOutcome::NearMiss { distance: 1, .. }), "expect a near-miss one edit away");
+    }
+
+    #[test]
+    fn evaluate_wrong() {
+        assert_eq!(evaluate(&word("apple"), "pear", &MatchPolicy::default()), GuessOutcome::Wrong, "expect the guess to be wrong");
+    }
+
+    #[test]
+    fn evaluate_closest_spelling() {
+        let mut word = word("colour");
+        word.alternatives.push("color".to_owned());
+
+        let outcome = evaluate(&word, "colr", &MatchPolicy::default());
+
+        assert!(matches!(outcome, GuessOutcome::NearMiss { distance: 1, .. }), "expect the closest spelling to be used");
+    }
+}
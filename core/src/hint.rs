@@ -0,0 +1,143 @@
+//! Hints that reveal part of the selected word.
+
+use std::fmt::Debug;
+use serde::{Deserialize, Serialize};
+
+/// What a hint reveals about the selected word.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Hint {
+    /// Shows how many letters the word has.
+    LetterCount,
+    /// Reveals the first letter.
+    FirstLetter,
+    /// Reveals the last letter.
+    LastLetter,
+    /// Reveals the first letter that is still hidden.
+    NextLetter,
+}
+
+/// The hints used for the currently selected word.
+#[derive(Default, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Hints {
+    used: usize,
+    revealed: Vec<usize>,
+}
+
+impl Hints {
+    /// Returns how many hints were used for the word.
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    /// Applies `hint` to `word` and returns the resulting pattern. Only letters are revealed, separators like `-` are
+    /// visible anyway. A hint that reveals nothing new is not counted as used.
+    pub fn reveal(&mut self, hint: Hint, word: &str) -> String {
+        let mut letters = word.chars()
+            .enumerate()
+            .filter(|(_, c)| c.is_alphabetic())
+            .map(|(position, _)| position);
+        let position = match hint {
+            Hint::LetterCount => None,
+            Hint::FirstLetter => letters.next(),
+            Hint::LastLetter => letters.last(),
+            Hint::NextLetter => letters.find(|position| !self.revealed.contains(position)),
+        };
+        // the first hint always shows the pattern and with it the letter count
+        let mut new = self.used == 0;
+        if let Some(position) = position {
+            if !self.revealed.contains(&position) {
+                self.revealed.push(position);
+                new = true;
+            }
+        }
+        if new {
+            self.used += 1;
+        }
+        self.pattern(word)
+    }
+
+    /// Returns `word` with every letter that was not revealed yet replaced by an underscore.
+    pub fn pattern(&self, word: &str) -> String {
+        word.chars()
+            .enumerate()
+            .map(|(position, c)| if !c.is_alphabetic() || self.revealed.contains(&position) { c } else { '_' })
+            .map(String::from)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn letter_count() {
+        let mut hints = Hints::default();
+
+        assert_eq!(hints.reveal(Hint::LetterCount, "apple"), "_ _ _ _ _", "expect every letter to be hidden");
+        assert_eq!(hints.used(), 1, "expect one hint to be used");
+    }
+
+    #[test]
+    fn first_and_last_letter() {
+        let mut hints = Hints::default();
+
+        hints.reveal(Hint::FirstLetter, "apple");
+
+        assert_eq!(hints.reveal(Hint::LastLetter, "apple"), "a _ _ _ e", "expect the first and last letter to be revealed");
+        assert_eq!(hints.used(), 2, "expect two hints to be used");
+    }
+
+    #[test]
+    fn next_letter() {
+        let mut hints = Hints::default();
+
+        hints.reveal(Hint::FirstLetter, "dog");
+        hints.reveal(Hint::NextLetter, "dog");
+
+        assert_eq!(hints.reveal(Hint::NextLetter, "dog"), "d o g", "expect the letters to be revealed from the left");
+    }
+
+    #[test]
+    fn skip_non_letters() {
+        let mut hints = Hints::default();
+
+        hints.reveal(Hint::NextLetter, "ice-cream");
+        hints.reveal(Hint::NextLetter, "ice-cream");
+        hints.reveal(Hint::NextLetter, "ice-cream");
+
+        assert_eq!(hints.reveal(Hint::NextLetter, "ice-cream"), "i c e - c _ _ _ _", "expect the hyphen to be skipped");
+        assert_eq!(hints.reveal(Hint::LastLetter, "ice cream!"), "i c e   c _ _ _ m !", "expect the last letter to be revealed instead of the mark");
+        assert_eq!(hints.used(), 5, "expect every hint that revealed a letter to be used");
+    }
+
+    #[test]
+    fn count_only_new_hints() {
+        let mut hints = Hints::default();
+
+        hints.reveal(Hint::LetterCount, "dog");
+        hints.reveal(Hint::LetterCount, "dog");
+        hints.reveal(Hint::FirstLetter, "dog");
+        hints.reveal(Hint::FirstLetter, "dog");
+        hints.reveal(Hint::NextLetter, "dog");
+        hints.reveal(Hint::LastLetter, "dog");
+
+        assert_eq!(hints.reveal(Hint::NextLetter, "dog"), "d o g", "expect every letter to be revealed");
+        assert_eq!(hints.used(), 4, "expect only the hints that revealed something new to be used");
+    }
+
+    #[test]
+    fn keep_non_letters() {
+        let hints = Hints::default();
+
+        assert_eq!(hints.pattern("ice-cream"), "_ _ _ - _ _ _ _ _", "expect non letters to stay visible");
+    }
+
+    #[test]
+    fn empty_word() {
+        let mut hints = Hints::default();
+
+        assert_eq!(hints.reveal(Hint::LastLetter, ""), "", "expect an empty pattern");
+    }
+}
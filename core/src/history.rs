@@ -0,0 +1,198 @@
+//! The log of every guess a learner made.
+
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_MAX_ENTRIES: usize = 10_000;
+const DEFAULT_MAX_AGE_DAYS: i64 = 365;
+
+/// A single checked guess.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Guess {
+    /// The canonical spelling of the word that was asked.
+    pub word: String,
+    /// What the learner typed.
+    pub guess: String,
+    /// Whether the guess matched a spelling of the word.
+    pub correct: bool,
+    /// When the guess was checked.
+    pub time: DateTime<Utc>,
+    /// How long the learner took since the word was asked or the previous guess.
+    pub duration: Option<Duration>,
+    /// The number of hints used for the word before this guess.
+    #[serde(default)]
+    pub hints: usize,
+}
+
+/// Limits how many guesses are kept in the save file. Older guesses are dropped first.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Retention {
+    /// How many guesses are kept at most.
+    pub max_entries: usize,
+    /// After how many days a guess is dropped, if ever.
+    pub max_age_days: Option<i64>,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            max_entries: DEFAULT_MAX_ENTRIES,
+            max_age_days: Some(DEFAULT_MAX_AGE_DAYS),
+        }
+    }
+}
+
+/// The guesses of a learner, oldest first, limited by a [Retention].
+#[derive(Default, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct History {
+    guesses: VecDeque<Guess>,
+    #[serde(default)]
+    retention: Retention,
+}
+
+impl History {
+    /// Builds a history from guesses stored elsewhere, oldest first. They are not pruned.
+    pub fn from_guesses(guesses: impl IntoIterator<Item = Guess>, retention: Retention) -> Self {
+        Self {
+            guesses: guesses.into_iter().collect(),
+            retention,
+        }
+    }
+
+    /// Returns the number of kept guesses.
+    pub fn len(&self) -> usize {
+        self.guesses.len()
+    }
+
+    /// Returns whether no guess is kept.
+    pub fn is_empty(&self) -> bool {
+        self.guesses.is_empty()
+    }
+
+    /// Returns the kept guesses, oldest first.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Guess> + '_ {
+        self.guesses.iter()
+    }
+
+    /// Returns the limits of how many and how old guesses are kept.
+    pub fn retention(&self) -> &Retention {
+        &self.retention
+    }
+
+    /// Changes how many guesses are kept and drops the ones that are too many or too old as of `now`.
+    pub fn set_retention(&mut self, retention: Retention, now: DateTime<Utc>) {
+        self.retention = retention;
+        self.prune(now);
+    }
+
+    /// Adds `guess` and drops the guesses that are too many or too old by its time.
+    pub fn push(&mut self, guess: Guess) {
+        let now = guess.time;
+        self.guesses.push_back(guess);
+        self.prune(now);
+    }
+
+    fn prune(&mut self, now: DateTime<Utc>) {
+        while self.guesses.len() > self.retention.max_entries {
+            self.guesses.pop_front();
+        }
+        // an age that cannot be represented is longer than any guess can be old, so everything is kept then
+        let oldest = self.retention.max_age_days
+            .and_then(chrono::Duration::try_days)
+            .and_then(|age| now.checked_sub_signed(age));
+        if let Some(oldest) = oldest {
+            while self.guesses.front().is_some_and(|guess| guess.time < oldest) {
+                self.guesses.pop_front();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+    use super::*;
+
+    fn guess(word: &str, time: DateTime<Utc>) -> Guess {
+        Guess {
+            word: word.to_owned(),
+            guess: word.to_owned(),
+            correct: true,
+            time,
+            duration: None,
+            hints: 0,
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, 8, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn empty_history() {
+        let history = History::default();
+
+        assert!(history.is_empty(), "expect no guesses to be logged");
+    }
+
+    #[test]
+    fn push_guesses_in_order() {
+        let mut history = History::default();
+
+        history.push(guess("apple", now()));
+        history.push(guess("dog", now()));
+
+        let words = history.iter().map(|guess| guess.word.as_str()).collect::<Vec<_>>();
+        assert_eq!(words, vec!["apple", "dog"], "expect the guesses in the order they were made");
+    }
+
+    #[test]
+    fn drop_guesses_beyond_max_entries() {
+        let mut history = History::default();
+        history.set_retention(Retention { max_entries: 2, max_age_days: None }, now());
+
+        history.push(guess("apple", now()));
+        history.push(guess("dog", now()));
+        history.push(guess("cat", now()));
+
+        let words = history.iter().map(|guess| guess.word.as_str()).collect::<Vec<_>>();
+        assert_eq!(words, vec!["dog", "cat"], "expect the oldest guess to be dropped");
+    }
+
+    #[test]
+    fn drop_guesses_beyond_max_age() {
+        let mut history = History::default();
+        history.set_retention(Retention { max_entries: 10, max_age_days: Some(7) }, now());
+
+        history.push(guess("apple", now() - chrono::Duration::days(8)));
+        history.push(guess("dog", now() - chrono::Duration::days(6)));
+        history.push(guess("cat", now()));
+
+        let words = history.iter().map(|guess| guess.word.as_str()).collect::<Vec<_>>();
+        assert_eq!(words, vec!["dog", "cat"], "expect guesses older than a week to be dropped");
+    }
+
+    #[test]
+    fn keep_guesses_for_huge_max_age() {
+        let mut history = History::default();
+        history.set_retention(Retention { max_entries: 10, max_age_days: Some(i64::MAX) }, now());
+
+        history.push(guess("apple", now() - chrono::Duration::days(8)));
+
+        assert_eq!(history.len(), 1, "expect an age beyond what can be represented to keep every guess");
+    }
+
+    #[test]
+    fn tighten_retention() {
+        let mut history = History::default();
+        history.push(guess("apple", now()));
+        history.push(guess("dog", now()));
+
+        history.set_retention(Retention { max_entries: 1, max_age_days: None }, now());
+
+        assert_eq!(history.len(), 1, "expect the history to be pruned to the new limit");
+    }
+}
@@ -0,0 +1,319 @@
+//! The on-disk cache of downloaded images and an in-memory cache of recently used ones.
+
+use std::collections::{HashSet, VecDeque};
+use std::fmt::Debug;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use http_cache_reqwest::{CACacheManager, CacheManager, HttpResponse};
+use http_cache_semantics::CachePolicy;
+
+type ManagerResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// How much is in the image cache.
+#[derive(Default, Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Usage {
+    /// The number of cached responses.
+    pub entries: usize,
+    /// The size of all cached responses in bytes.
+    pub size: u64,
+}
+
+/// Inspects and trims the on-disk http cache of downloaded images. As the cache manager of the http client it also
+/// records when a response was last used, so trimming drops the images that were not needed for the longest time.
+#[derive(Clone, Debug)]
+pub struct ImageCache {
+    path: PathBuf,
+}
+
+impl ImageCache {
+    /// Uses the cache in the folder `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    /// Returns how many responses are cached and how large they are.
+    pub fn usage(&self) -> cacache::Result<Usage> {
+        Ok(self.entries()?.iter().fold(Usage::default(), |usage, entry| Usage {
+            entries: usage.entries + 1,
+            size: usage.size + entry.size as u64,
+        }))
+    }
+
+    /// Removes the least recently used entries until the cache is at most `max_size` bytes large.
+    /// Returns what was removed.
+    pub fn evict(&self, max_size: u64) -> cacache::Result<Usage> {
+        let mut entries = self.entries()?;
+        entries.sort_by(|a, b| a.time.cmp(&b.time).then_with(|| a.key.cmp(&b.key)));
+
+        let mut size = entries.iter().map(|entry| entry.size as u64).sum::<u64>();
+        let mut removed = Usage::default();
+        let mut evicted = Vec::new();
+        for entry in &entries {
+            if size <= max_size {
+                break;
+            }
+            cacache::remove_sync(&self.path, &entry.key)?;
+            size -= entry.size as u64;
+            removed.entries += 1;
+            removed.size += entry.size as u64;
+            evicted.push(&entry.integrity);
+        }
+
+        // the content is shared by all keys with the same data, so it is only deleted once no kept key uses it
+        let kept = entries[removed.entries..].iter().map(|entry| entry.integrity.to_string()).collect::<HashSet<_>>();
+        let mut deleted = HashSet::new();
+        for integrity in evicted {
+            if !kept.contains(&integrity.to_string()) && deleted.insert(integrity.to_string()) {
+                cacache::remove_hash_sync(&self.path, integrity)?;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Removes every cached response.
+    pub fn clear(&self) -> cacache::Result<()> {
+        if self.path.exists() {
+            cacache::clear_sync(&self.path)?;
+        }
+        Ok(())
+    }
+
+    fn entries(&self) -> cacache::Result<Vec<cacache::Metadata>> {
+        match cacache::list_sync(&self.path).collect() {
+            Err(cacache::Error::IoError(error, _)) if error.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+            entries => entries,
+        }
+    }
+
+    fn manager(&self) -> CACacheManager {
+        CACacheManager {
+            path: self.path.clone(),
+        }
+    }
+
+    /// Writes the index entry of `key` again with the current time, keeping the content.
+    async fn touch(&self, key: &str) -> cacache::Result<()> {
+        let Some(entry) = cacache::index::find_async(&self.path, key).await? else {
+            return Ok(());
+        };
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+        let mut options = cacache::WriteOpts::new()
+            .integrity(entry.integrity)
+            .size(entry.size)
+            .metadata(entry.metadata)
+            .time(time);
+        if let Some(raw_metadata) = entry.raw_metadata {
+            options = options.raw_metadata(raw_metadata);
+        }
+        cacache::index::insert_async(&self.path, key, options).await?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl CacheManager for ImageCache {
+    async fn get(&self, cache_key: &str) -> ManagerResult<Option<(HttpResponse, CachePolicy)>> {
+        let cached = self.manager().get(cache_key).await?;
+        if cached.is_some() {
+            // a failed update only makes the entry look older than it is
+            let _ = self.touch(cache_key).await;
+        }
+        Ok(cached)
+    }
+
+    async fn put(&self, cache_key: String, response: HttpResponse, policy: CachePolicy) -> ManagerResult<HttpResponse> {
+        self.manager().put(cache_key, response, policy).await
+    }
+
+    async fn delete(&self, cache_key: &str) -> ManagerResult<()> {
+        self.manager().delete(cache_key).await
+    }
+}
+
+/// Keeps at most `capacity` values and drops the least recently used one first.
+#[derive(Clone, Debug)]
+pub struct Lru<K, V> {
+    capacity: usize,
+    entries: VecDeque<(K, V)>,
+}
+
+impl<K: PartialEq, V> Lru<K, V> {
+    /// Creates an empty cache that holds up to `capacity` values.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the value of `key` and marks it as the most recently used.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let position = self.entries.iter().position(|(other, _)| other == key)?;
+        let entry = self.entries.remove(position)?;
+        self.entries.push_front(entry);
+        self.entries.front().map(|(_, value)| value)
+    }
+
+    /// Adds `value` as the most recently used one, dropping the least recently used value if the cache is full.
+    pub fn insert(&mut self, key: K, value: V) {
+        self.entries.retain(|(other, _)| *other != key);
+        self.entries.push_front((key, value));
+        self.entries.truncate(self.capacity);
+    }
+
+    /// Drops every value whose key and value do not satisfy `keep`.
+    pub fn retain(&mut self, mut keep: impl FnMut(&K, &V) -> bool) {
+        self.entries.retain(|(key, value)| keep(key, value));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+    use super::*;
+
+    fn write(cache: &ImageCache, key: &str, size: usize, time: u128) -> cacache::Integrity {
+        write_data(cache, key, &vec![key.as_bytes()[0]; size], time)
+    }
+
+    fn write_data(cache: &ImageCache, key: &str, data: &[u8], time: u128) -> cacache::Integrity {
+        let mut writer = cacache::WriteOpts::new()
+            .time(time)
+            .size(data.len())
+            .open_sync(&cache.path, key)
+            .expect("writable cache");
+        writer.write_all(data).expect("writable entry");
+        writer.commit().expect("committed entry")
+    }
+
+    fn keys(cache: &ImageCache) -> Vec<String> {
+        let mut keys = cache.entries().expect("readable cache").into_iter().map(|entry| entry.key).collect::<Vec<_>>();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn lru_drops_least_recently_used() {
+        let mut lru = Lru::new(2);
+        lru.insert("a", 1);
+        lru.insert("b", 2);
+
+        assert_eq!(lru.get(&"a"), Some(&1), "expect the value to be found");
+        lru.insert("c", 3);
+
+        assert_eq!(lru.entries.len(), 2, "expect the capacity to be kept");
+        assert_eq!(lru.get(&"a"), Some(&1), "expect the recently used value to be kept");
+        assert_eq!(lru.get(&"b"), None, "expect the least recently used value to be dropped");
+    }
+
+    #[test]
+    fn lru_replaces_value() {
+        let mut lru = Lru::new(2);
+        lru.insert("a", 1);
+
+        lru.insert("a", 2);
+
+        assert_eq!(lru.get(&"a"), Some(&2), "expect the new value");
+        assert_eq!(lru.entries.len(), 1, "expect the key to be stored once");
+    }
+
+    #[test]
+    fn lru_retain() {
+        let mut lru = Lru::new(3);
+        lru.insert("a", 1);
+        lru.insert("b", 2);
+        lru.insert("c", 3);
+
+        lru.retain(|key, value| *key != "a" && *value != 3);
+
+        assert_eq!(lru.entries.iter().map(|(key, _)| *key).collect::<Vec<_>>(), vec!["b"], "expect only the kept value to remain");
+    }
+
+    #[test]
+    fn missing_cache() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let cache = ImageCache::new(dir.path().join("missing"));
+
+        assert_eq!(cache.usage().expect("readable cache"), Usage::default(), "expect an empty cache");
+        cache.clear().expect("clearable cache");
+    }
+
+    #[test]
+    fn usage() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let cache = ImageCache::new(dir.path());
+        write(&cache, "a", 10, 1);
+        write(&cache, "b", 20, 2);
+
+        assert_eq!(cache.usage().expect("readable cache"), Usage { entries: 2, size: 30 }, "expect every entry to be counted");
+    }
+
+    #[test]
+    fn evict_oldest_entries() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let cache = ImageCache::new(dir.path());
+        write(&cache, "b", 10, 2);
+        write(&cache, "a", 10, 1);
+        write(&cache, "c", 10, 3);
+
+        let removed = cache.evict(15).expect("evictable cache");
+
+        assert_eq!(removed, Usage { entries: 2, size: 20 }, "expect two entries to be removed");
+        assert_eq!(keys(&cache), vec!["c"], "expect the newest entry to be kept");
+        assert_eq!(cache.usage().expect("readable cache").size, 10, "expect the cache to fit the limit");
+    }
+
+    #[tokio::test]
+    async fn evict_least_recently_used() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let cache = ImageCache::new(dir.path());
+        write(&cache, "a", 10, 1);
+        write(&cache, "b", 10, 2);
+        write(&cache, "c", 10, 3);
+
+        cache.touch("a").await.expect("touchable entry");
+        cache.evict(15).expect("evictable cache");
+
+        assert_eq!(keys(&cache), vec!["a"], "expect the recently used entry to be kept");
+        assert_eq!(cacache::read_sync(&cache.path, "a").expect("readable entry"), vec![b'a'; 10], "expect the content to be kept");
+    }
+
+    #[test]
+    fn evict_keeps_shared_content() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let cache = ImageCache::new(dir.path());
+        let unique = write(&cache, "c", 10, 1);
+        let shared = write_data(&cache, "a", b"same image", 2);
+        write_data(&cache, "b", b"same image", 3);
+
+        cache.evict(10).expect("evictable cache");
+
+        assert_eq!(keys(&cache), vec!["b"], "expect the newest entry to be kept");
+        assert!(cacache::exists_sync(&cache.path, &shared), "expect the content of the kept entry to stay");
+        assert!(!cacache::exists_sync(&cache.path, &unique), "expect unused content to be deleted");
+    }
+
+    #[test]
+    fn evict_within_limit() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let cache = ImageCache::new(dir.path());
+        write(&cache, "a", 10, 1);
+
+        assert_eq!(cache.evict(10).expect("evictable cache"), Usage::default(), "expect nothing to be removed");
+        assert_eq!(keys(&cache), vec!["a"], "expect the entry to be kept");
+    }
+
+    #[test]
+    fn clear() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let cache = ImageCache::new(dir.path());
+        write(&cache, "a", 10, 1);
+
+        cache.clear().expect("clearable cache");
+
+        assert_eq!(cache.usage().expect("readable cache"), Usage::default(), "expect an empty cache");
+    }
+}
@@ -0,0 +1,171 @@
+//! Loads the pictures of the words from the web, from files and from data URLs. Downloads are cached on disk.
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use directories::ProjectDirs;
+use futures::StreamExt;
+use http_cache_reqwest::CacheMode;
+use lazy_static::lazy_static;
+use reqwest::StatusCode;
+use reqwest_middleware::ClientWithMiddleware;
+use thiserror::Error;
+use tokio::spawn;
+use url::Url;
+use crate::settings::NetworkSettings;
+use crate::source::{self, ImageSource};
+use crate::image::cache::ImageCache;
+use crate::image::network::{BodyTooLarge, NotAnImage, RetriesExhausted, TimedOut};
+
+pub mod cache;
+pub mod network;
+
+/// The size in bytes the image cache is trimmed to when the application exits.
+pub const MAX_CACHE_SIZE: u64 = 200 * 1024 * 1024;
+
+/// How many images are downloaded at the same time when prefetching or downloading a whole word list.
+pub const PREFETCH_CONCURRENCY: usize = 2;
+
+lazy_static!(
+    static ref CACHE_FOLDER: PathBuf = ProjectDirs::from("at.ac", "tgm", "spelling_trainer").expect("Failed to get project dirs").cache_dir().to_owned();
+);
+
+static CLIENTS: OnceLock<Clients> = OnceLock::new();
+
+struct Clients {
+    online: ClientWithMiddleware,
+    /// Only answers from the cache and never touches the network.
+    offline: ClientWithMiddleware,
+}
+
+impl Clients {
+    fn new(settings: NetworkSettings) -> Result<Self, network::Error> {
+        Ok(Self {
+            online: network::client(&settings, CacheMode::Default, CACHE_FOLDER.clone())?,
+            offline: network::client(&settings, CacheMode::OnlyIfCached, CACHE_FOLDER.clone())?,
+        })
+    }
+}
+
+fn clients() -> &'static Clients {
+    CLIENTS.get_or_init(|| Clients::new(NetworkSettings::default()).expect("Failed to build default http client"))
+}
+
+/// Sets up the http client with `settings`. Has no effect once the first image was downloaded.
+pub fn configure(settings: NetworkSettings) -> Result<(), network::Error> {
+    let clients = Clients::new(settings)?;
+    let _ = CLIENTS.set(clients);
+    Ok(())
+}
+
+/// Returns the on-disk cache of downloaded images.
+pub fn cache() -> ImageCache {
+    ImageCache::new(CACHE_FOLDER.as_path())
+}
+
+/// Reasons an image cannot be loaded.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// The request could not be sent or answered.
+    #[error(transparent)]
+    ReqwestError(#[from] reqwest::Error),
+    /// The cache or another part of the http client failed.
+    #[error(transparent)]
+    ReqwestMiddlewareError(#[from] reqwest_middleware::Error),
+    /// Reading an image file failed.
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    /// The image source is not valid.
+    #[error(transparent)]
+    SourceError(#[from] source::Error),
+    /// The image is not in the cache and nothing is downloaded while offline.
+    #[error("the image {0} has not been downloaded yet and the application is offline")]
+    NotCachedError(Url),
+    /// The server did not respond in time.
+    #[error("loading the image {0} timed out")]
+    TimeoutError(Url),
+    /// The download still failed after retrying.
+    #[error("failed to load the image {url} after {attempts} attempts: {message}")]
+    RetryError {
+        /// The url of the image.
+        url: Url,
+        /// How often the download was tried.
+        attempts: u32,
+        /// Why the last attempt failed.
+        message: String,
+    },
+    /// The server did not answer with the image.
+    #[error("the server responded to {0} with {1}")]
+    StatusError(Url, StatusCode),
+    /// The image is larger than images are allowed to be.
+    #[error("the image {url} is larger than {limit} bytes")]
+    BodyTooLargeError {
+        /// The url of the image.
+        url: Url,
+        /// The largest allowed size in bytes.
+        limit: u64,
+    },
+    /// The server did not answer with an image.
+    #[error("{url} is not an image but {content_type}")]
+    ContentTypeError {
+        /// The url of the image.
+        url: Url,
+        /// The type of the answer.
+        content_type: String,
+    },
+}
+
+impl Error {
+    fn from_request(url: Url, error: reqwest_middleware::Error) -> Self {
+        if let reqwest_middleware::Error::Middleware(error) = &error {
+            if let Some(exhausted) = error.chain().find_map(|error| error.downcast_ref::<RetriesExhausted>()) {
+                return Error::RetryError { url, attempts: exhausted.attempts, message: exhausted.last.clone() };
+            }
+            if let Some(BodyTooLarge(limit)) = error.chain().find_map(|error| error.downcast_ref::<BodyTooLarge>()) {
+                return Error::BodyTooLargeError { url, limit: *limit };
+            }
+            if let Some(NotAnImage(content_type)) = error.chain().find_map(|error| error.downcast_ref::<NotAnImage>()) {
+                return Error::ContentTypeError { url, content_type: content_type.clone() };
+            }
+            if error.chain().any(|error| error.is::<TimedOut>()) {
+                return Error::TimeoutError(url);
+            }
+        }
+        match error {
+            reqwest_middleware::Error::Reqwest(error) if error.is_timeout() => Error::TimeoutError(url),
+            error => Error::ReqwestMiddlewareError(error),
+        }
+    }
+}
+
+/// Downloads all `sources` into the cache, even in offline mode. Returns the result for every source in order.
+pub async fn download(sources: Vec<ImageSource>) -> Vec<Result<(), Error>> {
+    futures::stream::iter(sources)
+        .map(|source| async move { fetch(source, false).await.map(|_| ()) })
+        .buffered(PREFETCH_CONCURRENCY)
+        .collect()
+        .await
+}
+
+/// Reads the encoded image of `source`. Images from the web are cached on disk and only taken from the cache
+/// if `offline` is set.
+pub async fn fetch(source: ImageSource, offline: bool) -> Result<Vec<u8>, Error> {
+    let url = match source {
+        ImageSource::Web(url) => url,
+        ImageSource::File(path) | ImageSource::Relative(path) => return Ok(spawn(tokio::fs::read(path)).await.unwrap()?),
+        ImageSource::Data(url) => return Ok(source::decode(&url)?),
+    };
+
+    let clients = clients();
+    let client = if offline { &clients.offline } else { &clients.online };
+    let request = url.clone();
+    let response = spawn(async move {client.get(request).send().await}).await.unwrap()
+        .map_err(|error| Error::from_request(url.clone(), error))?;
+    if offline && response.status() == StatusCode::GATEWAY_TIMEOUT {
+        return Err(Error::NotCachedError(url));
+    }
+    if !response.status().is_success() {
+        return Err(Error::StatusError(url, response.status()));
+    }
+    // the size, read timeout and content type were already checked by the client before caching the response
+    Ok(response.bytes().await?.to_vec())
+}
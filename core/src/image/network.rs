@@ -0,0 +1,418 @@
+//! The http client that downloads images, with caching, timeouts and retries.
+
+use std::fmt::Debug;
+use std::path::PathBuf;
+use std::time::Duration;
+use http_cache_reqwest::{Cache, CacheMode, HttpCache, HttpCacheOptions};
+use reqwest::{Certificate, Client, Proxy, Request, Response, ResponseBuilderExt, StatusCode};
+use reqwest::header::CONTENT_TYPE;
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware, Middleware, Next};
+use task_local_extensions::Extensions;
+use thiserror::Error;
+use crate::image::cache::ImageCache;
+use crate::settings::NetworkSettings;
+
+/// Reasons the http client cannot be built from the settings.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// The proxy url is not valid.
+    #[error("invalid proxy {0}: {1}")]
+    ProxyError(String, reqwest::Error),
+    /// A root certificate cannot be read.
+    #[error("failed to read the certificate {0}: {1}")]
+    CertificateReadError(PathBuf, std::io::Error),
+    /// A root certificate is not a valid PEM certificate.
+    #[error("invalid certificate {0}: {1}")]
+    CertificateError(PathBuf, reqwest::Error),
+    /// The http client cannot be built.
+    #[error(transparent)]
+    ClientError(#[from] reqwest::Error),
+}
+
+/// Builds the http client for images with the cache in `cache_folder` and the proxy, certificates and
+/// timeouts of `settings`.
+pub fn client(settings: &NetworkSettings, mode: CacheMode, cache_folder: PathBuf) -> Result<ClientWithMiddleware, Error> {
+    let mut client = Client::builder()
+        .connect_timeout(settings.connect_timeout)
+        .timeout(settings.total_timeout)
+        .user_agent(&settings.user_agent);
+    if let Some(proxy) = &settings.proxy {
+        client = client.proxy(Proxy::all(proxy).map_err(|error| Error::ProxyError(proxy.clone(), error))?);
+    }
+    for path in &settings.ca_certificates {
+        let pem = std::fs::read(path).map_err(|error| Error::CertificateReadError(path.clone(), error))?;
+        let certificate = Certificate::from_pem(&pem).map_err(|error| Error::CertificateError(path.clone(), error))?;
+        client = client.add_root_certificate(certificate);
+    }
+
+    Ok(ClientBuilder::new(client.build()?)
+        .with(Cache(HttpCache {
+            mode,
+            manager: ImageCache::new(cache_folder),
+            options: HttpCacheOptions::default(),
+        }))
+        // the cache stores whatever reaches it, so the limits are checked before
+        .with(Limits {
+            max_body_size: settings.max_body_size,
+            read_timeout: settings.read_timeout,
+        })
+        .with(Retry {
+            retries: settings.retries,
+            backoff: settings.backoff,
+            max_backoff: settings.max_backoff,
+        })
+        .with(Timeout(settings.read_timeout))
+        .build())
+}
+
+/// The error of a request that ran into [Timeout].
+#[derive(Error, Debug)]
+#[error("no response within {0:?}")]
+pub struct TimedOut(pub Duration);
+
+/// The error of a request that still failed after the retries of [Retry].
+#[derive(Error, Debug)]
+#[error("gave up after {attempts} attempts: {last}")]
+pub struct RetriesExhausted {
+    /// How often the request was sent.
+    pub attempts: u32,
+    /// Why the last attempt failed.
+    pub last: String,
+}
+
+/// The error of a response whose body is larger than [Limits] allow.
+#[derive(Error, Debug)]
+#[error("the body is larger than {0} bytes")]
+pub struct BodyTooLarge(pub u64);
+
+/// The error of a successful response that is not an image.
+#[derive(Error, Debug)]
+#[error("unexpected content type {0}")]
+pub struct NotAnImage(pub String);
+
+/// Reads the whole body of responses and fails them if they are too large, too slow or not an image, so nothing
+/// like that is passed on to the cache.
+pub struct Limits {
+    /// The maximum size of a body in bytes.
+    pub max_body_size: u64,
+    /// How long to wait for every chunk of the body.
+    pub read_timeout: Duration,
+}
+
+#[async_trait::async_trait]
+impl Middleware for Limits {
+    async fn handle(&self, req: Request, extensions: &mut Extensions, next: Next<'_>) -> reqwest_middleware::Result<Response> {
+        let mut response = next.run(req, extensions).await?;
+        if response.status().is_success() {
+            let content_type = response.headers().get(CONTENT_TYPE)
+                .map(|content_type| String::from_utf8_lossy(content_type.as_bytes()).into_owned());
+            if let Some(content_type) = content_type.filter(|content_type| !is_image(content_type)) {
+                return Err(reqwest_middleware::Error::middleware(NotAnImage(content_type)));
+            }
+        }
+        if response.content_length().is_some_and(|length| length > self.max_body_size) {
+            return Err(reqwest_middleware::Error::middleware(BodyTooLarge(self.max_body_size)));
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = tokio::time::timeout(self.read_timeout, response.chunk()).await
+            .map_err(|_| reqwest_middleware::Error::middleware(TimedOut(self.read_timeout)))?? {
+            body.extend_from_slice(&chunk);
+            if body.len() as u64 > self.max_body_size {
+                return Err(reqwest_middleware::Error::middleware(BodyTooLarge(self.max_body_size)));
+            }
+        }
+
+        let mut builder = http::Response::builder()
+            .status(response.status())
+            .version(response.version())
+            .url(response.url().clone());
+        if let Some(headers) = builder.headers_mut() {
+            *headers = response.headers().clone();
+        }
+        let response = builder.body(body)
+            .map_err(reqwest_middleware::Error::middleware)?;
+        Ok(Response::from(response))
+    }
+}
+
+/// Servers that do not know better send images as binary data.
+fn is_image(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    mime.starts_with("image/") || mime == "application/octet-stream"
+}
+
+/// Fails a request if the server does not respond with the headers within the duration.
+pub struct Timeout(pub Duration);
+
+#[async_trait::async_trait]
+impl Middleware for Timeout {
+    async fn handle(&self, req: Request, extensions: &mut Extensions, next: Next<'_>) -> reqwest_middleware::Result<Response> {
+        tokio::time::timeout(self.0, next.run(req, extensions)).await
+            .unwrap_or_else(|_| Err(reqwest_middleware::Error::middleware(TimedOut(self.0))))
+    }
+}
+
+/// Repeats requests that failed for a transient reason, waiting twice as long before every further attempt.
+pub struct Retry {
+    /// How often a request is repeated at most.
+    pub retries: u32,
+    /// How long to wait before the first repetition.
+    pub backoff: Duration,
+    /// The longest wait between two attempts.
+    pub max_backoff: Duration,
+}
+
+impl Retry {
+    /// Returns how long to wait after the failed `attempt`, counted from zero.
+    fn delay(&self, attempt: u32) -> Duration {
+        2u32.checked_pow(attempt)
+            .map_or(self.max_backoff, |factor| self.backoff.saturating_mul(factor))
+            .min(self.max_backoff)
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for Retry {
+    async fn handle(&self, req: Request, extensions: &mut Extensions, next: Next<'_>) -> reqwest_middleware::Result<Response> {
+        let mut attempt = 0;
+        loop {
+            let Some(request) = req.try_clone() else {
+                return next.run(req, extensions).await;
+            };
+            let result = next.clone().run(request, extensions).await;
+            if attempt >= self.retries || !is_transient(&result) {
+                return match result {
+                    Err(error) if attempt > 0 => Err(reqwest_middleware::Error::middleware(RetriesExhausted {
+                        attempts: attempt + 1,
+                        last: error.to_string(),
+                    })),
+                    result => result,
+                };
+            }
+            tokio::time::sleep(self.delay(attempt)).await;
+            attempt += 1;
+        }
+    }
+}
+
+fn is_transient(result: &reqwest_middleware::Result<Response>) -> bool {
+    match result {
+        Ok(response) => response.status().is_server_error() || response.status() == StatusCode::TOO_MANY_REQUESTS,
+        Err(reqwest_middleware::Error::Reqwest(error)) => error.is_connect() || error.is_timeout() || error.is_request(),
+        Err(reqwest_middleware::Error::Middleware(error)) => error.chain().any(|error| error.is::<TimedOut>()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::task::JoinHandle;
+    use super::*;
+
+    async fn read_request(stream: &mut TcpStream) -> String {
+        let mut request = Vec::new();
+        let mut buffer = [0; 1024];
+        while !request.ends_with(b"\r\n\r\n") {
+            let read = stream.read(&mut buffer).await.expect("readable request");
+            if read == 0 {
+                break;
+            }
+            request.extend_from_slice(&buffer[..read]);
+        }
+        String::from_utf8_lossy(&request).into_owned()
+    }
+
+    /// Answers a single request like a proxy would and returns the request it received.
+    async fn stand_in_proxy(listener: TcpListener) -> String {
+        let (mut stream, _) = listener.accept().await.expect("incoming connection");
+        let request = read_request(&mut stream).await;
+        stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nContent-Length: 3\r\nConnection: close\r\n\r\npng").await.expect("writable response");
+        request
+    }
+
+    /// Answers one request after another with `responses`, keeping every connection open for the given time after
+    /// answering. Returns the url of the image and how many requests were answered.
+    async fn serve(responses: Vec<(&'static [u8], Duration)>) -> (String, JoinHandle<usize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("local listener");
+        let url = format!("http://{}/dog.png", listener.local_addr().expect("local address"));
+        let server = tokio::spawn(async move {
+            let mut answered = 0;
+            for (response, hold) in responses {
+                let (mut stream, _) = listener.accept().await.expect("incoming connection");
+                read_request(&mut stream).await;
+                stream.write_all(response).await.expect("writable response");
+                answered += 1;
+                tokio::time::sleep(hold).await;
+            }
+            answered
+        });
+        (url, server)
+    }
+
+    fn settings() -> NetworkSettings {
+        NetworkSettings {
+            retries: 0,
+            backoff: Duration::from_millis(1),
+            ..NetworkSettings::default()
+        }
+    }
+
+    fn caused_by<E: std::error::Error + 'static>(error: &reqwest_middleware::Error) -> bool {
+        match error {
+            reqwest_middleware::Error::Middleware(error) => error.chain().any(|error| error.is::<E>()),
+            reqwest_middleware::Error::Reqwest(_) => false,
+        }
+    }
+
+    fn cached_entries(cache: &Path) -> usize {
+        ImageCache::new(cache).usage().expect("readable cache").entries
+    }
+
+    #[tokio::test]
+    async fn request_through_proxy() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("local listener");
+        let settings = NetworkSettings {
+            proxy: Some(format!("http://{}", listener.local_addr().expect("local address"))),
+            user_agent: "classroom".to_owned(),
+            retries: 0,
+            ..NetworkSettings::default()
+        };
+        let cache = tempfile::tempdir().expect("temporary directory");
+        let client = client(&settings, CacheMode::NoStore, cache.path().to_owned()).expect("valid client");
+        let proxy = tokio::spawn(stand_in_proxy(listener));
+
+        let response = client.get("http://images.invalid/dog.png").send().await.expect("response from the proxy");
+        let body = response.bytes().await.expect("response body");
+        let request = proxy.await.expect("finished proxy");
+
+        assert_eq!(&body[..], b"png", "expect the body served by the proxy");
+        assert!(request.starts_with("GET http://images.invalid/dog.png HTTP/1.1"), "expect the request to be sent to the proxy");
+        assert!(request.to_ascii_lowercase().contains("user-agent: classroom"), "expect the configured user agent");
+    }
+
+    #[tokio::test]
+    async fn cache_image() {
+        let (url, server) = serve(vec![
+            (b"HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nContent-Length: 3\r\nCache-Control: max-age=3600\r\nConnection: close\r\n\r\npng", Duration::ZERO),
+        ]).await;
+        let cache = tempfile::tempdir().expect("temporary directory");
+        let client = client(&settings(), CacheMode::Default, cache.path().to_owned()).expect("valid client");
+
+        let first = client.get(&url).send().await.expect("downloaded image").bytes().await.expect("response body");
+        let second = client.get(&url).send().await.expect("cached image").bytes().await.expect("response body");
+
+        assert_eq!((&first[..], &second[..]), (&b"png"[..], &b"png"[..]), "expect the image both times");
+        assert_eq!(server.await.expect("finished server"), 1, "expect the second image to come from the cache");
+        assert_eq!(cached_entries(cache.path()), 1, "expect the image to be cached");
+    }
+
+    #[tokio::test]
+    async fn retry_server_error() {
+        let (url, server) = serve(vec![
+            (b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", Duration::ZERO),
+            (b"HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nContent-Length: 3\r\nConnection: close\r\n\r\npng", Duration::ZERO),
+        ]).await;
+        let settings = NetworkSettings {
+            retries: 2,
+            ..settings()
+        };
+        let cache = tempfile::tempdir().expect("temporary directory");
+        let client = client(&settings, CacheMode::NoStore, cache.path().to_owned()).expect("valid client");
+
+        let response = client.get(&url).send().await.expect("response after retrying");
+
+        assert_eq!(response.status(), StatusCode::OK, "expect the retried request to succeed");
+        assert_eq!(server.await.expect("finished server"), 2, "expect the request to be sent twice");
+    }
+
+    #[tokio::test]
+    async fn slow_body_times_out() {
+        let (url, _server) = serve(vec![
+            (b"HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nContent-Length: 10\r\nCache-Control: max-age=3600\r\nConnection: close\r\n\r\npng", Duration::from_secs(5)),
+        ]).await;
+        let settings = NetworkSettings {
+            read_timeout: Duration::from_millis(200),
+            ..settings()
+        };
+        let cache = tempfile::tempdir().expect("temporary directory");
+        let client = client(&settings, CacheMode::Default, cache.path().to_owned()).expect("valid client");
+
+        let error = client.get(&url).send().await.unwrap_err();
+
+        assert!(caused_by::<TimedOut>(&error), "expect the stalled body to time out, got {error}");
+        assert_eq!(cached_entries(cache.path()), 0, "expect nothing to be cached");
+    }
+
+    #[tokio::test]
+    async fn oversized_body() {
+        let (url, _server) = serve(vec![
+            (b"HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nCache-Control: max-age=3600\r\nConnection: close\r\n\r\nmuch too large", Duration::ZERO),
+        ]).await;
+        let settings = NetworkSettings {
+            max_body_size: 4,
+            ..settings()
+        };
+        let cache = tempfile::tempdir().expect("temporary directory");
+        let client = client(&settings, CacheMode::Default, cache.path().to_owned()).expect("valid client");
+
+        let error = client.get(&url).send().await.unwrap_err();
+
+        assert!(caused_by::<BodyTooLarge>(&error), "expect the body without a length to be too large, got {error}");
+        assert_eq!(cached_entries(cache.path()), 0, "expect nothing to be cached");
+    }
+
+    #[tokio::test]
+    async fn wrong_content_type() {
+        let (url, _server) = serve(vec![
+            (b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 6\r\nCache-Control: max-age=3600\r\nConnection: close\r\n\r\n<html>", Duration::ZERO),
+        ]).await;
+        let cache = tempfile::tempdir().expect("temporary directory");
+        let client = client(&settings(), CacheMode::Default, cache.path().to_owned()).expect("valid client");
+
+        let error = client.get(&url).send().await.unwrap_err();
+
+        assert!(caused_by::<NotAnImage>(&error), "expect the page to be rejected, got {error}");
+        assert_eq!(cached_entries(cache.path()), 0, "expect nothing to be cached");
+    }
+
+    #[test]
+    fn backoff_is_limited() {
+        let retry = Retry {
+            retries: 40,
+            backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+        };
+
+        assert_eq!(retry.delay(0), Duration::from_secs(1), "expect the first delay to be the backoff");
+        assert_eq!(retry.delay(2), Duration::from_secs(4), "expect the delay to double");
+        assert_eq!(retry.delay(5), Duration::from_secs(30), "expect the delay to be limited");
+        assert_eq!(retry.delay(35), Duration::from_secs(30), "expect large attempts not to overflow");
+    }
+
+    #[test]
+    fn invalid_proxy() {
+        let settings = NetworkSettings {
+            proxy: Some("not a proxy".to_owned()),
+            ..NetworkSettings::default()
+        };
+
+        let error = client(&settings, CacheMode::NoStore, PathBuf::new()).unwrap_err();
+
+        assert!(matches!(error, Error::ProxyError(..)), "expect the proxy to be rejected");
+    }
+
+    #[test]
+    fn missing_certificate() {
+        let settings = NetworkSettings {
+            ca_certificates: vec![PathBuf::from("/does/not/exist.pem")],
+            ..NetworkSettings::default()
+        };
+
+        let error = client(&settings, CacheMode::NoStore, PathBuf::new()).unwrap_err();
+
+        assert!(matches!(error, Error::CertificateReadError(..)), "expect the missing certificate to be reported");
+    }
+}
@@ -0,0 +1,374 @@
+//! Reads word lists from CSV, JSON and TOML files.
+
+use std::fmt::Debug;
+use std::path::Path;
+use std::str::FromStr;
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use thiserror::Error;
+use toml::Spanned;
+use crate::matching::MatchPolicy;
+use crate::source::ImageSource;
+use crate::Word;
+
+/// The file formats of word lists.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    /// A table with the columns `word`, `alternatives`, `url` and `credits`.
+    Csv,
+    /// An array of words, or an object with a `words` array and an optional `policy`.
+    Json,
+    /// A `words` array of tables and an optional `policy` table.
+    Toml,
+}
+
+impl Format {
+    /// Returns the format given by the extension of `path`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "csv" => Some(Format::Csv),
+            "json" => Some(Format::Json),
+            "toml" => Some(Format::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// An invalid word in a word list.
+#[derive(Error, Debug)]
+#[error("line {line}: {message}")]
+pub struct RowError {
+    /// The line the word starts on.
+    pub line: usize,
+    /// What is wrong with the word.
+    pub message: String,
+}
+
+/// Reasons a word list cannot be imported.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Reading the file failed.
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    /// The file extension is not one of the supported formats.
+    #[error("unsupported file type, expected a .csv, .json or .toml file")]
+    UnsupportedFormatError,
+    /// The word list has no words.
+    #[error("the word list is empty")]
+    EmptyError,
+    /// The file is not valid CSV.
+    #[error(transparent)]
+    CsvError(#[from] csv::Error),
+    /// The file is not valid JSON.
+    #[error(transparent)]
+    JsonError(#[from] serde_json::Error),
+    /// The file is not valid TOML.
+    #[error(transparent)]
+    TomlError(#[from] toml::de::Error),
+    /// Some words are invalid, all of them are listed.
+    #[error("{}", .0.iter().map(RowError::to_string).collect::<Vec<_>>().join("\n"))]
+    ValidationError(Vec<RowError>),
+}
+
+#[derive(Deserialize)]
+struct Row {
+    word: String,
+    #[serde(default)]
+    alternatives: Vec<String>,
+    url: String,
+    #[serde(default)]
+    credits: String,
+}
+
+/// CSV has no lists, so alternative spellings are separated by `|` in a single column.
+#[derive(Deserialize)]
+struct CsvRow {
+    word: String,
+    #[serde(default)]
+    alternatives: String,
+    url: String,
+    #[serde(default)]
+    credits: String,
+}
+
+#[derive(Deserialize)]
+struct JsonFile<'a> {
+    #[serde(default)]
+    policy: Option<MatchPolicy>,
+    #[serde(borrow)]
+    words: Vec<&'a RawValue>,
+}
+
+#[derive(Deserialize)]
+struct TomlFile {
+    #[serde(default)]
+    policy: Option<MatchPolicy>,
+    words: Vec<Spanned<Row>>,
+}
+
+/// The words of an imported word list.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WordList {
+    /// The valid words in the order of the file.
+    pub words: Vec<Word>,
+    /// How guesses should be compared, if the word list says so.
+    pub policy: Option<MatchPolicy>,
+}
+
+/// Reads the word list at `path` in the format given by its extension.
+pub fn import(path: &Path) -> Result<WordList, Error> {
+    let format = Format::from_path(path).ok_or(Error::UnsupportedFormatError)?;
+    let text = std::fs::read_to_string(path)?;
+    parse(format, &text)
+}
+
+/// Reads a word list in `format` from `text`. Every invalid word is reported, not only the first one.
+pub fn parse(format: Format, text: &str) -> Result<WordList, Error> {
+    let (rows, policy) = match format {
+        Format::Csv => (parse_csv(text)?, None),
+        Format::Json => parse_json(text)?,
+        Format::Toml => parse_toml(text)?,
+    };
+
+    let mut words = Vec::new();
+    let mut errors = Vec::new();
+    for row in rows {
+        match row.and_then(|(line, row)| validate(line, row)) {
+            Ok(word) => words.push(word),
+            Err(error) => errors.push(error),
+        }
+    }
+
+    if !errors.is_empty() {
+        Err(Error::ValidationError(errors))
+    } else if words.is_empty() {
+        Err(Error::EmptyError)
+    } else {
+        Ok(WordList { words, policy })
+    }
+}
+
+type Rows = Vec<Result<(usize, Row), RowError>>;
+
+fn parse_csv(text: &str) -> Result<Rows, Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+    let headers = reader.headers()?.clone();
+
+    let mut rows = Vec::new();
+    let mut record = csv::StringRecord::new();
+    while reader.read_record(&mut record)? {
+        let line = record.position().map_or(0, |position| position.line() as usize);
+        let row = record.deserialize::<CsvRow>(Some(&headers))
+            .map(|row| (line, Row {
+                word: row.word,
+                alternatives: row.alternatives.split('|').map(str::to_owned).collect(),
+                url: row.url,
+                credits: row.credits,
+            }))
+            .map_err(|error| RowError { line, message: error.to_string() });
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+fn parse_json(text: &str) -> Result<(Rows, Option<MatchPolicy>), Error> {
+    let (values, policy) = if text.trim_start().starts_with('{') {
+        let file: JsonFile = serde_json::from_str(text)?;
+        (file.words, file.policy)
+    } else {
+        (serde_json::from_str::<Vec<&RawValue>>(text)?, None)
+    };
+
+    let rows = values.into_iter()
+        .map(|value| {
+            let offset = value.get().as_ptr() as usize - text.as_ptr() as usize;
+            let line = line_of(text, offset);
+            serde_json::from_str::<Row>(value.get())
+                .map(|row| (line, row))
+                .map_err(|error| RowError { line, message: error.to_string() })
+        })
+        .collect();
+    Ok((rows, policy))
+}
+
+fn parse_toml(text: &str) -> Result<(Rows, Option<MatchPolicy>), Error> {
+    let file: TomlFile = toml::from_str(text)?;
+
+    let rows = file.words.into_iter()
+        .map(|row| Ok((line_of(text, row.span().start), row.into_inner())))
+        .collect();
+    Ok((rows, file.policy))
+}
+
+fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset].matches('\n').count() + 1
+}
+
+fn validate(line: usize, row: Row) -> Result<Word, RowError> {
+    let word = row.word.trim();
+    if word.is_empty() {
+        return Err(RowError { line, message: "the word is empty".to_owned() });
+    }
+    let url = ImageSource::from_str(&row.url)
+        .map_err(|error| RowError { line, message: error.to_string() })?;
+
+    Ok(Word {
+        word: word.to_owned(),
+        alternatives: row.alternatives.iter()
+            .map(|alternative| alternative.trim())
+            .filter(|alternative| !alternative.is_empty())
+            .map(str::to_owned)
+            .collect(),
+        url,
+        credits: row.credits.trim().to_owned(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::matching::Normalization;
+    use super::*;
+
+    fn lines(error: Error) -> Vec<usize> {
+        match error {
+            Error::ValidationError(errors) => errors.iter().map(|error| error.line).collect(),
+            error => panic!("expected a validation error, got {error:?}"),
+        }
+    }
+
+    #[test]
+    fn format_from_path() {
+        assert_eq!(Format::from_path(Path::new("words.CSV")), Some(Format::Csv), "expect the extension to be case insensitive");
+        assert_eq!(Format::from_path(Path::new("words.json")), Some(Format::Json), "expect json to be detected");
+        assert_eq!(Format::from_path(Path::new("words.toml")), Some(Format::Toml), "expect toml to be detected");
+        assert_eq!(Format::from_path(Path::new("words.txt")), None, "expect an unknown extension to be rejected");
+    }
+
+    #[test]
+    fn parse_csv_words() {
+        let text = "word,url,credits,alternatives\n\
+            colour, https://colour.org/ ,Colour,color\n\
+            dog,https://dog.org/,,\n";
+
+        let words = parse(Format::Csv, text).expect("valid word list").words;
+
+        assert_eq!(words.len(), 2, "expect two words");
+        assert_eq!(words[0].word, "colour", "expect the word to be read");
+        assert_eq!(words[0].alternatives, vec!["color"], "expect the alternatives to be read");
+        assert_eq!(words[0].url.to_string(), "https://colour.org/", "expect the url to be trimmed");
+        assert!(words[1].alternatives.is_empty(), "expect no alternatives");
+    }
+
+    #[test]
+    fn parse_image_sources() {
+        let text = "word,url\n\
+            dog,images/dog.png\n\
+            cat,file:///images/cat.png\n\
+            pig,\"data:image/png;base64,AQID\"\n";
+
+        let words = parse(Format::Csv, text).expect("valid word list").words;
+
+        assert!(matches!(words[0].url, ImageSource::Relative(_)), "expect a path relative to the word list");
+        assert!(matches!(words[1].url, ImageSource::File(_)), "expect a file url");
+        assert!(matches!(words[2].url, ImageSource::Data(_)), "expect a data url");
+    }
+
+    #[test]
+    fn parse_csv_errors() {
+        let text = "word,url,credits\n\
+            apple,https://apple.com/,Apple\n\
+            ,https://empty.org/,Empty\n\
+            dog,ftp://dog.org/,Dog\n";
+
+        assert_eq!(lines(parse(Format::Csv, text).unwrap_err()), vec![3, 4], "expect the invalid lines to be reported");
+    }
+
+    #[test]
+    fn parse_json_words() {
+        let text = r#"[
+            {"word": "colour", "alternatives": ["color"], "url": "https://colour.org/", "credits": "Colour"},
+            {"word": "dog", "url": "https://dog.org/"}
+        ]"#;
+
+        let words = parse(Format::Json, text).expect("valid word list").words;
+
+        assert_eq!(words.len(), 2, "expect two words");
+        assert_eq!(words[0].alternatives, vec!["color"], "expect the alternatives to be read");
+        assert_eq!(words[1].credits, "", "expect missing credits to be empty");
+    }
+
+    #[test]
+    fn parse_json_errors() {
+        let text = r#"[
+            {"word": "apple", "url": "https://apple.com/"},
+            {"word": "dog"},
+            {
+                "word": "cat",
+                "url": "http://"
+            }
+        ]"#;
+
+        assert_eq!(lines(parse(Format::Json, text).unwrap_err()), vec![3, 4], "expect the invalid entries to be reported");
+    }
+
+    #[test]
+    fn parse_toml_words() {
+        let text = r#"
+            [[words]]
+            word = "colour"
+            alternatives = ["color"]
+            url = "https://colour.org/"
+            credits = "Colour"
+
+            [[words]]
+            word = "dog"
+            url = "https://dog.org/"
+        "#;
+
+        let words = parse(Format::Toml, text).expect("valid word list").words;
+
+        assert_eq!(words.len(), 2, "expect two words");
+        assert_eq!(words[0].alternatives, vec!["color"], "expect the alternatives to be read");
+    }
+
+    #[test]
+    fn parse_toml_errors() {
+        let text = "[[words]]\nword = \"apple\"\nurl = \"https://apple.com/\"\n\n[[words]]\nword = \" \"\nurl = \"https://empty.org/\"\n";
+
+        assert_eq!(lines(parse(Format::Toml, text).unwrap_err()), vec![5], "expect the invalid table to be reported");
+    }
+
+    #[test]
+    fn parse_json_policy() {
+        let text = r#"{
+            "policy": {"case_sensitive": false, "transliterate_umlauts": true},
+            "words": [{"word": "Käse", "url": "https://cheese.org/"}]
+        }"#;
+
+        let list = parse(Format::Json, text).expect("valid word list");
+
+        assert_eq!(list.words.len(), 1, "expect the words to be read");
+        assert_eq!(list.policy, Some(MatchPolicy {
+            case_sensitive: false,
+            transliterate_umlauts: true,
+            ..Default::default()
+        }), "expect the policy to be read with defaults for missing fields");
+        assert_eq!(parse(Format::Json, r#"[{"word": "dog", "url": "https://dog.org/"}]"#).expect("valid word list").policy, None, "expect an array to have no policy");
+    }
+
+    #[test]
+    fn parse_toml_policy() {
+        let text = "[policy]\nnormalization = \"Nfkc\"\n\n[[words]]\nword = \"fish\"\nurl = \"https://fish.org/\"\n";
+
+        let list = parse(Format::Toml, text).expect("valid word list");
+
+        assert_eq!(list.policy.map(|policy| policy.normalization), Some(Normalization::Nfkc), "expect the policy table to be read");
+    }
+
+    #[test]
+    fn parse_empty_list() {
+        assert!(matches!(parse(Format::Json, "[]"), Err(Error::EmptyError)), "expect an empty list to be rejected");
+    }
+}
@@ -0,0 +1,865 @@
+//! The core of the spelling trainer: the word list with the progress of a learner, how guesses are checked and
+//! scored, and how all of it is stored, imported and exported. [image] fetches the pictures of the words.
+//!
+//! The crate knows nothing about a user interface. A frontend keeps a [Trainer], shows the picture of
+//! [Trainer::selected] and passes the learner's input to [Trainer::guess]:
+//!
+//! ```
+//! use spelling_trainer_core::{Trainer, Word};
+//!
+//! let mut trainer = Trainer::new(vec![Word::new("dog", "https://example.org/dog.png".parse().expect("valid url"))]);
+//! trainer.random();
+//! assert!(trainer.guess("dog").is_correct());
+//! assert_eq!(trainer.statistic().correct(), 1);
+//! ```
+//!
+//! Progress is kept per learner profile in a [storage::Storage], either as CBOR files ([save]) or in an SQLite
+//! database.
+
+#![warn(missing_docs)]
+
+pub mod statistic;
+pub mod history;
+pub mod image;
+pub mod bundle;
+pub mod export;
+pub mod feedback;
+pub mod hint;
+pub mod import;
+pub mod matching;
+pub mod profile;
+pub mod save;
+pub mod settings;
+pub mod source;
+pub mod storage;
+mod schedule;
+
+use std::fmt::Debug;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::feedback::GuessOutcome;
+use crate::hint::{Hint, Hints};
+use crate::history::{Guess, History, Retention};
+use crate::matching::MatchPolicy;
+use crate::schedule::Schedule;
+use crate::source::ImageSource;
+pub use crate::statistic::{Statistic, WordStatistic};
+
+/// A word to practice together with the picture shown for it.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Word {
+    /// The canonical spelling.
+    pub word: String,
+    /// Further spellings that are accepted as correct.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alternatives: Vec<String>,
+    /// Where the picture comes from. Relative paths are resolved against the directory of the word list.
+    pub url: ImageSource,
+    /// Who made the picture, shown in the about dialog.
+    pub credits: String,
+}
+
+/// The number of failed loads in a row after which an image is reported as broken.
+pub const BROKEN_IMAGE_FAILURES: u32 = 3;
+
+impl Word {
+    /// Creates a word without alternative spellings or credits.
+    pub fn new(word: impl Into<String>, url: ImageSource) -> Self {
+        Self {
+            word: word.into(),
+            alternatives: Vec::new(),
+            url,
+            credits: String::new(),
+        }
+    }
+
+    /// Accepts `alternatives` as correct spellings as well.
+    pub fn with_alternatives(mut self, alternatives: Vec<String>) -> Self {
+        self.alternatives = alternatives;
+        self
+    }
+
+    /// Credits the picture to `credits`.
+    pub fn with_credits(mut self, credits: impl Into<String>) -> Self {
+        self.credits = credits.into();
+        self
+    }
+
+    /// Returns the canonical spelling followed by all alternative spellings.
+    pub fn spellings(&self) -> impl Iterator<Item = &str> + '_ {
+        std::iter::once(self.word.as_str())
+            .chain(self.alternatives.iter().map(String::as_str))
+    }
+}
+
+lazy_static!{
+    /// The words a new profile practices until a word list is imported.
+    pub static ref STARTER_WORDS: [Word; 4] = [
+        Word {
+            word: "apple".to_owned(),
+            alternatives: Vec::new(),
+            url: ImageSource::from_str("https://external-content.duckduckgo.com/iu/?u=http%3A%2F%2Fwikiclipart.com%2Fwp-content%2Fuploads%2F2016%2F09%2Fclip-art-apple-free-clipart.png&f=1&nofb=1&ipt=8d2d625dc550c18588574defee43dedc1906b3ff464fb1afab521c3426ed6f0e&ipo=images").expect("Failed to parse builtin image url"),
+            credits: "apple https://wikiclipart.com/apple-clipart_480/".to_owned(),
+        },
+        Word {
+            word: "raspberry".to_owned(),
+            alternatives: Vec::new(),
+            url: ImageSource::from_str("https://external-content.duckduckgo.com/iu/?u=http%3A%2F%2Fclipartmag.com%2Fimages%2Fraspberry-clipart-27.png&f=1&nofb=1&ipt=e7b96c092eb38787f696e47f6c80cb23b5e7c299a538f01a099597fb06ba0f21&ipo=images").expect("Failed to parse builtin image url"),
+            credits: "raspberry https://clipartmag.com/download-clipart-image#raspberry-clipart-27.png".to_owned(),
+        },
+        Word {
+            word: "dog".to_owned(),
+            alternatives: Vec::new(),
+            url: ImageSource::from_str("https://external-content.duckduckgo.com/iu/?u=https%3A%2F%2Fwallpaperboat.com%2Fwp-content%2Fuploads%2F2021%2F05%2F13%2F77274%2Fdoge-meme-11.jpg&f=1&nofb=1&ipt=2c90776ba562173dcbda96b9fa10110e2ec577700a7f8ec511dc9a2825b644b9&ipo=images").expect("Failed to parse builtin image url"),
+            credits: "dog https://wallpaperboat.com/doge-meme-wallpapers".to_owned(),
+        },
+        Word {
+            word: "cat".to_owned(),
+            alternatives: Vec::new(),
+            url: ImageSource::from_str("https://external-content.duckduckgo.com/iu/?u=https%3A%2F%2Fthecaninebuddy.com%2Fwp-content%2Fuploads%2F2021%2F08%2Fcrying-cat-meme.jpg&f=1&nofb=1&ipt=e2f2214f7587939060fef5208b166c8e19269d2a4b92b6185f8f83119bff266b&ipo=images").expect("Failed to parse builtin image url"),
+            credits: "cat https://thecaninebuddy.com/crying-cat-meme-know-when-you-should-use-it/".to_owned(),
+        }
+    ];
+}
+
+/// A practice session over a word list: which word is asked, how the guesses are checked and everything the
+/// learner achieved so far. This is what gets saved.
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct Trainer {
+    words: Vec<Word>,
+    selected: Option<usize>,
+    statistic: Statistic,
+    #[serde(default)]
+    schedule: Schedule,
+    #[serde(default)]
+    missed: bool,
+    #[serde(default)]
+    hints: Hints,
+    #[serde(default)]
+    asked: Option<DateTime<Utc>>,
+    #[serde(default)]
+    history: History,
+    #[serde(default)]
+    policy: MatchPolicy,
+    /// The directory relative image paths of the words are resolved against.
+    #[serde(default)]
+    directory: Option<PathBuf>,
+    /// How often in a row the image of a word failed to load, by spelling. Words whose image loads are left out.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    image_failures: BTreeMap<String, u32>,
+    #[serde(skip)]
+    previous: Option<usize>,
+    /// Whether anything worth saving changed since the last save.
+    #[serde(skip)]
+    dirty: bool,
+    #[serde(skip)]
+    generator: Generator,
+}
+
+/// Picks the random words. Unlike [ThreadRng] it can be sent to another thread together with the trainer.
+#[derive(Clone, Debug)]
+struct Generator(StdRng);
+
+impl Default for Generator {
+    fn default() -> Self {
+        Self(StdRng::from_entropy())
+    }
+}
+
+impl PartialEq for Trainer {
+    fn eq(&self, other: &Self) -> bool {
+        self.words == other.words &&
+            self.selected == other.selected &&
+            self.statistic == other.statistic &&
+            self.schedule == other.schedule &&
+            self.missed == other.missed &&
+            self.hints == other.hints &&
+            self.asked == other.asked &&
+            self.history == other.history &&
+            self.policy == other.policy &&
+            self.directory == other.directory &&
+            self.image_failures == other.image_failures
+    }
+}
+
+impl Trainer {
+    /// Starts practicing `words` without any progress.
+    pub fn new(words: impl Into<Vec<Word>>) -> Self {
+        Self {
+            words: words.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the words in the order they were given.
+    pub fn words(&self) -> &[Word] {
+        &self.words
+    }
+
+    /// Replaces the word list. Progress is kept for words whose spelling is in both lists.
+    pub fn set_words(&mut self, words: impl Into<Vec<Word>>) {
+        let words = words.into();
+        let mapping = words.iter()
+            .map(|word| self.words.iter().position(|old| old.word == word.word))
+            .collect::<Vec<_>>();
+        self.schedule.remap(&mapping);
+        self.statistic.remap_words(&mapping);
+        self.image_failures.retain(|spelling, _| words.iter().any(|word| word.word == *spelling));
+        self.words = words;
+        self.dirty = true;
+        self.selected = None;
+        self.previous = None;
+        self.missed = false;
+        self.hints = Hints::default();
+    }
+
+    /// Returns the word that is asked at the moment.
+    pub fn selected(&self) -> Option<&Word> {
+        match self.selected {
+            Some(idx) if idx < self.words.len() => Some(&self.words[idx]),
+            _ => None,
+        }
+    }
+
+    /// Asks the word at `idx` in [Trainer::words].
+    pub fn select(&mut self, idx: usize) -> Option<&Word> {
+        self.select_at(idx, Utc::now())
+    }
+
+    #[doc(hidden)]
+    pub fn select_at(&mut self, idx: usize, now: DateTime<Utc>) -> Option<&Word> {
+        self.dirty = true;
+        self.selected = Some(idx);
+        self.missed = false;
+        self.hints = Hints::default();
+        self.asked = Some(now);
+        self.selected()
+    }
+
+    /// Asks the next word, preferring words that are due according to the learner's progress.
+    pub fn random(&mut self) -> Option<&Word> {
+        self.random_at(Utc::now())
+    }
+
+    #[doc(hidden)]
+    pub fn random_at(&mut self, now: DateTime<Utc>) -> Option<&Word> {
+        let candidates = self.schedule.candidates(self.words.len(), now, self.previous);
+        match candidates.choose(&mut self.generator.0) {
+            Some(&idx) => {
+                self.select_at(idx, now);
+            }
+            None => {
+                self.selected = None;
+                self.dirty = true;
+            }
+        }
+
+        self.selected()
+    }
+
+    /// Returns up to `count` words that are likely to be selected next.
+    pub fn upcoming(&self, count: usize) -> Vec<&Word> {
+        self.upcoming_at(count, Utc::now())
+    }
+
+    #[doc(hidden)]
+    pub fn upcoming_at(&self, count: usize, now: DateTime<Utc>) -> Vec<&Word> {
+        self.schedule.candidates(self.words.len(), now, self.selected.or(self.previous))
+            .into_iter()
+            .take(count)
+            .map(|idx| &self.words[idx])
+            .collect()
+    }
+
+    /// Selects another word without counting a guess for the selected one.
+    pub fn skip(&mut self) -> Option<&Word> {
+        self.skip_at(Utc::now())
+    }
+
+    #[doc(hidden)]
+    pub fn skip_at(&mut self, now: DateTime<Utc>) -> Option<&Word> {
+        self.previous = self.selected.take().or(self.previous);
+        self.random_at(now)
+    }
+
+    /// Counts a failed load of the image of `word` and returns how often it failed in a row, or nothing if the word
+    /// is not in the list.
+    pub fn record_image_failure(&mut self, word: &str) -> Option<u32> {
+        let word = self.words.iter().find(|other| other.word == word)?;
+        let failures = self.image_failures.entry(word.word.clone()).or_default();
+        *failures += 1;
+        self.dirty = true;
+        Some(*failures)
+    }
+
+    /// Resets the failed loads of the image of `word`.
+    pub fn record_image_loaded(&mut self, word: &str) {
+        if self.image_failures.remove(word).is_some() {
+            self.dirty = true;
+        }
+    }
+
+    /// Returns how often in a row the image of `word` failed to load.
+    pub fn image_failures(&self, word: &str) -> u32 {
+        self.image_failures.get(word).copied().unwrap_or_default()
+    }
+
+    /// Returns whether the image of `word` failed to load so often that it is probably broken.
+    pub fn has_broken_image(&self, word: &str) -> bool {
+        self.image_failures(word) >= BROKEN_IMAGE_FAILURES
+    }
+
+    /// Returns all words whose image repeatedly failed to load.
+    pub fn broken_images(&self) -> impl Iterator<Item = &Word> + '_ {
+        self.words.iter().filter(|word| self.has_broken_image(&word.word))
+    }
+
+    /// Checks `guess` against the selected word and records the result in the statistic and history.
+    pub fn guess(&mut self, guess: &str) -> GuessOutcome {
+        self.guess_at(guess, Utc::now())
+    }
+
+    #[doc(hidden)]
+    pub fn guess_at(&mut self, guess: &str, now: DateTime<Utc>) -> GuessOutcome {
+        self.dirty = true;
+        let outcome = self.selected()
+            .map_or(GuessOutcome::Wrong, |word| feedback::evaluate(word, guess, &self.policy));
+        let correct = outcome.is_correct();
+        if let Some(word) = self.selected() {
+            let guess = Guess {
+                word: word.word.clone(),
+                guess: guess.to_owned(),
+                correct,
+                time: now,
+                duration: self.asked.and_then(|asked| (now - asked).to_std().ok()),
+                hints: self.hints.used(),
+            };
+            self.history.push(guess);
+            self.asked = Some(now);
+        }
+        let unaided = !self.missed && self.hints.used() == 0;
+        if let Some(idx) = self.selected {
+            self.statistic.word_mut(idx).record(correct, unaided, now);
+            let review = self.schedule.review_mut(idx);
+            if !correct {
+                review.demote(now);
+                self.missed = true;
+            } else if unaided {
+                review.promote(now);
+            }
+        }
+        if correct {
+            self.statistic.increment_correct();
+            if self.hints.used() > 0 {
+                self.statistic.increment_hinted();
+            }
+            self.previous = self.selected.take();
+        } else if outcome.is_near_miss() {
+            self.statistic.increment_near_miss();
+        } else {
+            self.statistic.increment_incorrect();
+        }
+        outcome
+    }
+
+    /// Reveals part of the selected word and returns it with the hidden letters replaced by underscores. A hint that
+    /// reveals nothing new is not counted.
+    pub fn hint(&mut self, hint: Hint) -> Option<String> {
+        let idx = self.selected.filter(|&idx| idx < self.words.len())?;
+        let used = self.hints.used();
+        let pattern = self.hints.reveal(hint, &self.words[idx].word);
+        if self.hints.used() > used {
+            self.dirty = true;
+            self.statistic.increment_hints();
+            self.statistic.word_mut(idx).record_hint();
+        }
+        Some(pattern)
+    }
+
+    /// Returns what the learner achieved over all words.
+    pub fn statistic(&self) -> &Statistic {
+        &self.statistic
+    }
+
+    /// Returns every word together with what the learner achieved with it.
+    pub fn word_statistics(&self) -> impl Iterator<Item = (&Word, WordStatistic)> + '_ {
+        self.words.iter()
+            .enumerate()
+            .map(|(idx, word)| (word, self.statistic.word(idx)))
+    }
+
+    /// Returns how strictly guesses are compared to the spellings.
+    pub fn match_policy(&self) -> &MatchPolicy {
+        &self.policy
+    }
+
+    /// Changes how strictly the following guesses are compared to the spellings.
+    pub fn set_match_policy(&mut self, policy: MatchPolicy) {
+        self.policy = policy;
+        self.dirty = true;
+    }
+
+    /// Returns the directory relative image paths are resolved against, usually the one of the word list.
+    pub fn directory(&self) -> Option<&Path> {
+        self.directory.as_deref()
+    }
+
+    /// Resolves relative image paths against `directory` from now on.
+    pub fn set_directory(&mut self, directory: Option<PathBuf>) {
+        self.directory = directory;
+        self.dirty = true;
+    }
+
+    /// Returns the image source of `word` with relative paths resolved against the word list directory.
+    pub fn image_source(&self, word: &Word) -> ImageSource {
+        word.url.resolve(self.directory())
+    }
+
+    /// Returns the words with their image sources resolved, so they stay valid wherever the words are written to.
+    pub fn resolved_words(&self) -> Vec<Word> {
+        self.words.iter()
+            .map(|word| Word {
+                url: self.image_source(word),
+                ..word.clone()
+            })
+            .collect()
+    }
+
+    /// Returns the guesses of the learner that are still kept.
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// Changes how long guesses are kept and forgets the ones that are too old now.
+    pub fn set_history_retention(&mut self, retention: Retention) {
+        self.history.set_retention(retention, Utc::now());
+        self.dirty = true;
+    }
+
+    /// Forgets what the learner achieved. The history of guesses is kept.
+    pub fn reset_statistic(&mut self) {
+        self.statistic = Statistic::default();
+        self.dirty = true;
+    }
+
+    /// Returns whether the trainer changed since it was restored or last marked as saved.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Marks the trainer as unchanged after it was handed to a save.
+    pub fn mark_saved(&mut self) {
+        self.dirty = false;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+    use chrono::{Duration, TimeZone};
+    use lazy_static::lazy_static;
+    use url::Url;
+    use super::*;
+
+    lazy_static! {
+        static ref WORDS: [Word; 2] = [
+            Word {
+                word: "Apple".to_owned(),
+                alternatives: Vec::new(),
+                url: ImageSource::from_str("https://apple.com/").expect("valid url"),
+                credits: "Apple".to_owned(),
+            },
+            Word {
+                word: "Raspberry".to_owned(),
+                alternatives: Vec::new(),
+                url: ImageSource::from_str("https://raspberry.org/").expect("valid url"),
+                credits: "Raspberry".to_owned(),
+            }
+        ];
+    }
+
+    #[test]
+    fn default_selected() {
+        let trainer = Trainer::default();
+
+        assert_eq!(trainer.selected(), None, "expect an empty selection");
+    }
+
+    #[test]
+    fn select_from_empty_wordlist() {
+        let mut trainer = Trainer::default();
+        trainer.select(0);
+
+        assert_eq!(trainer.selected(), None, "expect an empty selection");
+    }
+
+    #[test]
+    fn select_invalid_index() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+        trainer.select(2);
+
+        assert_eq!(trainer.selected(), None, "expect an empty selection");
+    }
+
+    #[test]
+    fn select_valid_index() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+        trainer.select(0);
+
+        assert_eq!(trainer.selected(), Some(&WORDS[0]), "expect the first word to be selected");
+    }
+
+    #[test]
+    fn select_random_index_from_empty_wordlist() {
+        let mut trainer = Trainer::default();
+        trainer.random();
+
+        assert_eq!(trainer.selected(), None, "expect an empty selection");
+    }
+
+    #[test]
+    fn select_random_index_from_words() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+        trainer.random();
+
+        assert!(trainer.selected().is_some_and(|word| trainer.words.contains(word)), "expect a random word to be selected");
+    }
+
+    #[test]
+    fn correct_guess() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+        trainer.select(0);
+
+        assert!(trainer.guess(&WORDS[0].word).is_correct(), "expect the guess to be correct");
+        assert_eq!(trainer.selected(), None, "expect the selection to be cleared");
+        assert_eq!(trainer.statistic().correct(), 1, "expect one correct guess to be counted");
+        assert_eq!(trainer.statistic().incorrect(), 0, "expect no incorrect guess to be counted");
+    }
+
+    #[test]
+    fn incorrect_guess() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+        trainer.select(0);
+
+        assert!(!trainer.guess(&WORDS[1].word).is_correct(), "expect the guess to be incorrect");
+        assert_eq!(trainer.selected(), Some(&WORDS[0]), "expect the first word to stay selected");
+        assert_eq!(trainer.statistic().correct(), 0, "expect no correct guess to be counted");
+        assert_eq!(trainer.statistic().incorrect(), 1, "expect one incorrect guess to be counted");
+    }
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, 8, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn correct_guess_schedules_word_later() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+        trainer.select(0);
+
+        trainer.guess_at(&WORDS[0].word, now());
+
+        assert_eq!(trainer.schedule.review(0).level(), 1, "expect the word to move up one box");
+        assert!(!trainer.schedule.review(0).is_due(now()), "expect the word not to be due right away");
+    }
+
+    #[test]
+    fn incorrect_guess_schedules_word_again() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+        trainer.select(0);
+        trainer.guess_at(&WORDS[0].word, now());
+        trainer.select(0);
+
+        trainer.guess_at(&WORDS[1].word, now());
+        trainer.guess_at(&WORDS[0].word, now());
+
+        assert_eq!(trainer.schedule.review(0).level(), 0, "expect the word to fall back to the first box");
+        assert!(trainer.schedule.review(0).is_due(now()), "expect the word to be due immediately");
+    }
+
+    #[test]
+    fn random_skips_known_word() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+        trainer.select(1);
+        trainer.guess_at(&WORDS[1].word, now() - Duration::hours(1));
+        trainer.select(0);
+        trainer.guess_at(&WORDS[0].word, now());
+
+        assert_eq!(trainer.random_at(now()), Some(&WORDS[1]), "expect the word that is due to be selected");
+    }
+
+    #[test]
+    fn random_does_not_repeat_previous_word() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+        trainer.select(0);
+        trainer.guess_at(&WORDS[1].word, now());
+        trainer.guess_at(&WORDS[0].word, now());
+
+        assert_eq!(trainer.random_at(now()), Some(&WORDS[1]), "expect the other word to be selected");
+    }
+
+    #[test]
+    fn schedule_survives_save() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+        trainer.select(0);
+        trainer.guess_at(&WORDS[0].word, now());
+
+        let mut save = Vec::new();
+        ciborium::into_writer(&trainer, &mut save).expect("serializable trainer");
+        let restored: Trainer = ciborium::from_reader(save.as_slice()).expect("deserializable trainer");
+
+        assert_eq!(restored, trainer, "expect the restored trainer to equal the saved one");
+        assert_eq!(restored.schedule.review(0).level(), 1, "expect the review state to be restored");
+    }
+
+    #[test]
+    fn word_statistics() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+        trainer.select(0);
+        trainer.guess_at(&WORDS[1].word, now());
+        trainer.guess_at(&WORDS[0].word, now());
+        trainer.select(1);
+        trainer.guess_at(&WORDS[1].word, now());
+
+        let statistics = trainer.word_statistics().collect::<Vec<_>>();
+        assert_eq!(statistics.len(), 2, "expect a statistic for every word");
+        assert_eq!(statistics[0].0, &WORDS[0], "expect the statistics in word order");
+        assert_eq!(statistics[0].1.attempts(), 2, "expect two attempts for the first word");
+        assert_eq!(statistics[0].1.first_try(), 0, "expect no first try success for the first word");
+        assert_eq!(statistics[1].1.attempts(), 1, "expect one attempt for the second word");
+        assert_eq!(statistics[1].1.first_try(), 1, "expect one first try success for the second word");
+        assert_eq!(statistics[1].1.last_seen(), Some(now()), "expect the second word to be seen now");
+    }
+
+    #[test]
+    fn guesses_are_logged() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+        trainer.select_at(0, now());
+        trainer.guess_at(&WORDS[1].word, now() + Duration::seconds(5));
+        trainer.guess_at(&WORDS[0].word, now() + Duration::seconds(7));
+
+        let history = trainer.history().iter().collect::<Vec<_>>();
+        assert_eq!(history.len(), 2, "expect every guess to be logged");
+        assert_eq!(history[0].word, WORDS[0].word, "expect the asked word to be logged");
+        assert_eq!(history[0].guess, WORDS[1].word, "expect the typed text to be logged");
+        assert!(!history[0].correct, "expect the first guess to be logged as incorrect");
+        assert_eq!(history[0].duration, Some(std::time::Duration::from_secs(5)), "expect the time since the word was asked");
+        assert!(history[1].correct, "expect the second guess to be logged as correct");
+        assert_eq!(history[1].time, now() + Duration::seconds(7), "expect the time of the guess to be logged");
+        assert_eq!(history[1].duration, Some(std::time::Duration::from_secs(2)), "expect the time since the previous guess");
+    }
+
+    #[test]
+    fn history_survives_reset() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+        trainer.select(0);
+        trainer.guess(&WORDS[0].word);
+
+        trainer.reset_statistic();
+
+        assert_eq!(trainer.history().len(), 1, "expect the history to be kept");
+    }
+
+    #[test]
+    fn guess_with_match_policy() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+        trainer.select(0);
+
+        assert!(!trainer.guess("apple").is_correct(), "expect the case to matter by default");
+
+        trainer.set_match_policy(MatchPolicy {
+            case_sensitive: false,
+            ..Default::default()
+        });
+
+        assert!(trainer.guess(" apple").is_correct(), "expect the guess to match with the relaxed policy");
+    }
+
+    #[test]
+    fn guess_alternative_spelling() {
+        let word = Word::new("colour", ImageSource::from_str("https://colour.org/").expect("valid url"))
+            .with_alternatives(vec!["color".to_owned()])
+            .with_credits("Colour");
+        assert_eq!(word.spellings().collect::<Vec<_>>(), vec!["colour", "color"], "expect both spellings");
+        let mut trainer = Trainer::new(vec![word]);
+        trainer.select(0);
+
+        assert!(!trainer.guess("colr").is_correct(), "expect an unknown spelling to be incorrect");
+        assert!(trainer.guess("color").is_correct(), "expect the alternative spelling to be correct");
+        trainer.select(0);
+        assert!(trainer.guess("colour").is_correct(), "expect the canonical spelling to be correct");
+    }
+
+    #[test]
+    fn restore_word_without_alternatives() {
+        #[derive(Serialize)]
+        struct LegacyWord {
+            word: String,
+            url: Url,
+            credits: String,
+        }
+
+        let legacy = LegacyWord {
+            word: WORDS[0].word.clone(),
+            url: Url::from_str("https://apple.com/").expect("valid url"),
+            credits: WORDS[0].credits.clone(),
+        };
+        let mut save = Vec::new();
+        ciborium::into_writer(&legacy, &mut save).expect("serializable word");
+        let word: Word = ciborium::from_reader(save.as_slice()).expect("deserializable word");
+
+        assert_eq!(word, WORDS[0], "expect a plain word to be restored without alternatives");
+    }
+
+    #[test]
+    fn resolve_image_source() {
+        let word = Word {
+            word: "dog".to_owned(),
+            alternatives: Vec::new(),
+            url: ImageSource::from_str("images/dog.png").expect("valid url"),
+            credits: String::new(),
+        };
+        let mut trainer = Trainer::new(vec![word.clone()]);
+
+        assert_eq!(trainer.image_source(&word), word.url, "expect a relative path without a directory");
+        trainer.set_directory(Some(PathBuf::from("/words")));
+        assert_eq!(trainer.image_source(&word), ImageSource::File(PathBuf::from("/words/images/dog.png")), "expect the path to be resolved against the directory");
+    }
+
+    #[test]
+    fn upcoming_words() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+        trainer.select_at(0, now());
+
+        assert_eq!(trainer.upcoming_at(5, now()), vec![&WORDS[1]], "expect the other word to be upcoming");
+        assert!(trainer.upcoming_at(0, now()).is_empty(), "expect no words to be returned");
+    }
+
+    #[test]
+    fn skip_word() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+        trainer.select(0);
+
+        assert_eq!(trainer.skip(), Some(&WORDS[1]), "expect another word to be selected");
+        assert_eq!(trainer.statistic().total(), 0, "expect no guess to be counted");
+        assert!(trainer.history().is_empty(), "expect no guess to be logged");
+    }
+
+    #[test]
+    fn report_broken_images() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+
+        for _ in 0..BROKEN_IMAGE_FAILURES {
+            trainer.record_image_failure("Apple");
+        }
+        trainer.record_image_failure("Raspberry");
+
+        let broken = trainer.broken_images().map(|word| word.word.as_str()).collect::<Vec<_>>();
+        assert_eq!(broken, vec!["Apple"], "expect only the repeatedly failing image to be reported");
+        assert_eq!(trainer.image_failures("Raspberry"), 1, "expect the failures to be counted per word");
+        assert_eq!(trainer.record_image_failure("Banana"), None, "expect an unknown word to be ignored");
+        trainer.record_image_loaded("Apple");
+        assert_eq!(trainer.broken_images().count(), 0, "expect a loaded image to reset the failures");
+        assert_eq!(trainer.words(), &WORDS[..], "expect the words to be unchanged");
+    }
+
+    #[test]
+    fn near_miss_guess() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+        trainer.select(1);
+
+        let outcome = trainer.guess("Rapsberry");
+
+        assert!(outcome.is_near_miss(), "expect the guess to be a near-miss");
+        assert_eq!(trainer.selected(), Some(&WORDS[1]), "expect the second word to stay selected");
+        assert_eq!(trainer.statistic().near_misses(), 1, "expect one near-miss to be counted");
+        assert_eq!(trainer.statistic().incorrect(), 1, "expect one incorrect guess to be counted");
+    }
+
+    #[test]
+    fn hint_without_selection() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+
+        assert_eq!(trainer.hint(Hint::FirstLetter), None, "expect no hint without a selected word");
+        assert_eq!(trainer.statistic().hints(), 0, "expect no hint to be counted");
+    }
+
+    #[test]
+    fn hinted_guess() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+        trainer.select(0);
+
+        assert_eq!(trainer.hint(Hint::FirstLetter), Some("A _ _ _ _".to_owned()), "expect the first letter to be revealed");
+        assert!(trainer.guess_at(&WORDS[0].word, now()).is_correct(), "expect the guess to be correct");
+
+        assert_eq!(trainer.statistic().hints(), 1, "expect one hint to be counted");
+        assert_eq!(trainer.statistic().hinted(), 1, "expect one hinted guess to be counted");
+        assert_eq!(trainer.statistic().word(0).hints(), 1, "expect one hint to be counted for the word");
+        assert_eq!(trainer.statistic().word(0).first_try(), 0, "expect the hinted guess not to count as unaided");
+        assert_eq!(trainer.history().iter().last().map(|guess| guess.hints), Some(1), "expect the hint to be logged");
+        assert_eq!(trainer.schedule.review(0).level(), 0, "expect the word not to move up a box");
+    }
+
+    #[test]
+    fn repeated_hint_not_counted() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+        trainer.select(0);
+
+        trainer.hint(Hint::FirstLetter);
+
+        assert_eq!(trainer.hint(Hint::FirstLetter), Some("A _ _ _ _".to_owned()), "expect the pattern to stay the same");
+        assert_eq!(trainer.statistic().hints(), 1, "expect the repeated hint not to be counted");
+        assert_eq!(trainer.statistic().word(0).hints(), 1, "expect the repeated hint not to be counted for the word");
+    }
+
+    #[test]
+    fn hints_reset_on_select() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+        trainer.select(0);
+        trainer.hint(Hint::LastLetter);
+
+        trainer.select(1);
+
+        assert_eq!(trainer.hint(Hint::LetterCount), Some("_ _ _ _ _ _ _ _ _".to_owned()), "expect no letters of the new word to be revealed");
+    }
+
+    #[test]
+    fn set_words_keeps_progress() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+        trainer.select(1);
+        trainer.guess_at(&WORDS[1].word, now());
+
+        trainer.set_words(vec![WORDS[1].clone()]);
+
+        assert_eq!(trainer.words(), &WORDS[1..], "expect the word list to be replaced");
+        assert_eq!(trainer.selected(), None, "expect the selection to be cleared");
+        assert_eq!(trainer.statistic().word(0).attempts(), 1, "expect the statistic of the kept word to move along");
+        assert_eq!(trainer.schedule.review(0).level(), 1, "expect the review of the kept word to move along");
+    }
+
+    #[test]
+    fn dirty_after_changes() {
+        let mut trainer = Trainer::new(&WORDS[..]);
+        assert!(!trainer.is_dirty(), "expect a new trainer to be clean");
+
+        trainer.select(0);
+        trainer.guess_at(&WORDS[0].word, now());
+        assert!(trainer.is_dirty(), "expect a guess to make the trainer dirty");
+
+        trainer.mark_saved();
+        assert!(!trainer.is_dirty(), "expect a saved trainer to be clean");
+        trainer.record_image_loaded(&WORDS[0].word);
+        assert!(!trainer.is_dirty(), "expect a change without effect to keep the trainer clean");
+
+        trainer.reset_statistic();
+        assert!(trainer.is_dirty(), "expect a reset to make the trainer dirty");
+        trainer.mark_saved();
+        trainer.set_words(vec![WORDS[1].clone()]);
+        assert!(trainer.is_dirty(), "expect a new word list to make the trainer dirty");
+    }
+}
@@ -0,0 +1,206 @@
+//! How strictly guesses are compared to the spellings of a word.
+
+use std::fmt::Debug;
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+/// How Unicode text is normalized before it is compared.
+#[derive(Default, Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Normalization {
+    /// Compares the text as it is typed.
+    None,
+    /// Composes letters and their accents, so both ways of typing `é` match.
+    #[default]
+    Nfc,
+    /// Also replaces compatibility characters like ligatures and full width letters by their plain forms.
+    Nfkc,
+}
+
+/// Decides which guesses count as a correct spelling of a word. Missing fields are read as their defaults.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MatchPolicy {
+    /// Whether upper and lower case letters differ.
+    pub case_sensitive: bool,
+    /// Whether spaces around the guess are ignored.
+    pub trim_whitespace: bool,
+    /// How the spellings and guesses are normalized.
+    pub normalization: Normalization,
+    /// Treats `ä`, `ö`, `ü` and `ß` the same as `ae`, `oe`, `ue` and `ss`.
+    pub transliterate_umlauts: bool,
+}
+
+impl Default for MatchPolicy {
+    fn default() -> Self {
+        Self {
+            case_sensitive: true,
+            trim_whitespace: true,
+            normalization: Normalization::default(),
+            transliterate_umlauts: false,
+        }
+    }
+}
+
+impl MatchPolicy {
+    /// Returns `text` in the form it is compared in.
+    pub fn normalize(&self, text: &str) -> String {
+        let text = if self.trim_whitespace { text.trim() } else { text };
+        let mut text = match self.normalization {
+            Normalization::None => text.to_owned(),
+            Normalization::Nfc => text.nfc().collect(),
+            Normalization::Nfkc => text.nfkc().collect(),
+        };
+        if self.transliterate_umlauts {
+            text = transliterate(&text.nfc().collect::<Vec<_>>());
+        }
+        if !self.case_sensitive {
+            text = text.to_lowercase();
+        }
+        text
+    }
+
+    /// Returns whether `guess` counts as the spelling `expected`.
+    pub fn matches(&self, expected: &str, guess: &str) -> bool {
+        self.normalize(expected) == self.normalize(guess)
+    }
+}
+
+/// Replaces umlauts and `ß` by their two letter spellings. A capital umlaut within an upper case word becomes
+/// `AE` instead of `Ae`, so `ÄPFEL` and `AEPFEL` stay the same with case sensitive matching.
+fn transliterate(chars: &[char]) -> String {
+    let mut text = String::with_capacity(chars.len());
+    for (idx, &c) in chars.iter().enumerate() {
+        let (first, second) = match c {
+            'ä' => ('a', 'e'),
+            'ö' => ('o', 'e'),
+            'ü' => ('u', 'e'),
+            'Ä' => ('A', 'e'),
+            'Ö' => ('O', 'e'),
+            'Ü' => ('U', 'e'),
+            'ß' => ('s', 's'),
+            'ẞ' => ('S', 'S'),
+            c => {
+                text.push(c);
+                continue;
+            }
+        };
+        text.push(first);
+        text.push(if first.is_uppercase() && upper_case_word(chars, idx) { second.to_ascii_uppercase() } else { second });
+    }
+    text
+}
+
+/// Returns whether the word around `idx` is written in upper case, judged by the next letter or, at the end of the
+/// word, by the previous one.
+fn upper_case_word(chars: &[char], idx: usize) -> bool {
+    let next = chars.get(idx + 1).filter(|c| c.is_alphabetic());
+    let previous = idx.checked_sub(1).map(|idx| &chars[idx]).filter(|c| c.is_alphabetic());
+    next.or(previous).is_some_and(|c| c.is_uppercase())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn default_policy() {
+        let policy = MatchPolicy::default();
+
+        assert!(policy.matches("apple", "apple"), "expect the same spelling to match");
+        assert!(!policy.matches("apple", "Apple"), "expect the case to matter");
+        assert!(policy.matches("apple", " apple "), "expect surrounding whitespace to be ignored");
+    }
+
+    #[test]
+    fn case_insensitive() {
+        let policy = MatchPolicy {
+            case_sensitive: false,
+            ..Default::default()
+        };
+
+        assert!(policy.matches("apple", "APPLE"), "expect the case to be ignored");
+        assert!(policy.matches("Straße", "STRAßE"), "expect the case of umlauts to be ignored");
+    }
+
+    #[test]
+    fn keep_whitespace() {
+        let policy = MatchPolicy {
+            trim_whitespace: false,
+            ..Default::default()
+        };
+
+        assert!(!policy.matches("apple", " apple"), "expect surrounding whitespace to matter");
+    }
+
+    #[test]
+    fn nfc_normalization() {
+        let policy = MatchPolicy::default();
+
+        assert!(policy.matches("K\u{e4}se", "Ka\u{308}se"), "expect composed and decomposed umlauts to match");
+        assert!(!policy.matches("\u{fb01}sh", "fish"), "expect compatibility characters to differ");
+    }
+
+    #[test]
+    fn nfkc_normalization() {
+        let policy = MatchPolicy {
+            normalization: Normalization::Nfkc,
+            ..Default::default()
+        };
+
+        assert!(policy.matches("fish", "\u{fb01}sh"), "expect ligatures to match their letters");
+        assert!(policy.matches("K\u{e4}se", "Ka\u{308}se"), "expect composed and decomposed umlauts to match");
+    }
+
+    #[test]
+    fn no_normalization() {
+        let policy = MatchPolicy {
+            normalization: Normalization::None,
+            ..Default::default()
+        };
+
+        assert!(!policy.matches("K\u{e4}se", "Ka\u{308}se"), "expect composed and decomposed umlauts to differ");
+    }
+
+    #[test]
+    fn transliterate_umlauts() {
+        let policy = MatchPolicy {
+            transliterate_umlauts: true,
+            ..Default::default()
+        };
+
+        assert!(policy.matches("Käse", "Kaese"), "expect ae to match ä");
+        assert!(policy.matches("Kaese", "Käse"), "expect ä to match ae");
+        assert!(policy.matches("Straße", "Strasse"), "expect ss to match ß");
+        assert!(policy.matches("Übung", "Uebung"), "expect Ue to match Ü");
+        assert!(policy.matches("Ka\u{308}se", "Kaese"), "expect decomposed umlauts to be transliterated");
+        assert!(!policy.matches("Käse", "Kase"), "expect a missing umlaut to differ");
+    }
+
+    #[test]
+    fn transliterate_upper_case_umlauts() {
+        let policy = MatchPolicy {
+            transliterate_umlauts: true,
+            ..Default::default()
+        };
+
+        assert!(policy.matches("ÄPFEL", "AEPFEL"), "expect AE to match Ä in an upper case word");
+        assert!(policy.matches("MÜ", "MUE"), "expect UE to match Ü at the end of an upper case word");
+        assert!(policy.matches("Äpfel", "Aepfel"), "expect Ae to match Ä in a capitalized word");
+        assert!(policy.matches("Ü", "Ue"), "expect Ue to match a single Ü");
+        assert!(!policy.matches("ÄPFEL", "AePFEL"), "expect the case of the transliteration to matter");
+    }
+
+    #[test]
+    fn partial_policy() {
+        let policy: MatchPolicy = serde_json::from_str(r#"{"case_sensitive": false}"#).expect("valid policy");
+
+        assert_eq!(policy, MatchPolicy { case_sensitive: false, ..Default::default() }, "expect missing fields to be defaults");
+    }
+
+    #[test]
+    fn keep_umlauts() {
+        let policy = MatchPolicy::default();
+
+        assert!(!policy.matches("Straße", "Strasse"), "expect ss not to match ß");
+    }
+}
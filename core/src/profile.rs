@@ -0,0 +1,359 @@
+//! Learner profiles, each with their own progress.
+
+use std::fmt::Debug;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use crate::save;
+
+/// The save file used before there were profiles. It becomes the save of the first profile.
+const LEGACY_SAVE: &str = "save.cbor";
+const INDEX: &str = "profiles.toml";
+const PROFILE_FOLDER: &str = "profiles";
+/// The name of the profile that is created when there is none.
+pub const DEFAULT_NAME: &str = "Default";
+
+/// Reasons the profiles cannot be read or changed.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Reading or writing the profile list failed.
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    /// The profile list is not valid.
+    #[error("the profile list is damaged: {0}")]
+    DeserializeError(#[from] toml::de::Error),
+    /// The profile list cannot be written.
+    #[error("failed to write the profile list: {0}")]
+    SerializeError(#[from] toml::ser::Error),
+    /// A profile needs a name.
+    #[error("the profile name is empty")]
+    EmptyNameError,
+    /// Another profile has the same name, ignoring case.
+    #[error("there already is a profile called \"{0}\"")]
+    DuplicateNameError(String),
+    /// There is no profile with the id.
+    #[error("the profile does not exist")]
+    UnknownProfileError(u32),
+}
+
+/// A learner with their own progress.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    /// Identifies the save file of the profile, so it stays the same when the profile is renamed.
+    pub id: u32,
+    /// The name the learner is shown as.
+    pub name: String,
+}
+
+/// The profiles of a save folder. Every change is written to the folder right away.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Profiles {
+    profiles: Vec<Profile>,
+    /// The profile that was used last.
+    last: Option<u32>,
+    next_id: u32,
+    #[serde(skip)]
+    folder: PathBuf,
+}
+
+impl Profiles {
+    /// Reads the profiles of `folder`. Without any profiles, a default one is created.
+    pub fn load(folder: &Path) -> Result<Self, Error> {
+        let mut profiles = match std::fs::read_to_string(folder.join(INDEX)) {
+            Ok(text) => toml::from_str(&text)?,
+            Err(error) if error.kind() == ErrorKind::NotFound => Self {
+                profiles: Vec::new(),
+                last: None,
+                next_id: 1,
+                folder: folder.to_owned(),
+            },
+            Err(error) => return Err(error.into()),
+        };
+        profiles.folder = folder.to_owned();
+        profiles.adopt_legacy_save()?;
+        Ok(profiles)
+    }
+
+    /// Rebuilds the profiles of `folder` from the save files in it, e.g. if the profile list is damaged. The
+    /// names of the profiles are lost.
+    pub fn recover(folder: &Path) -> Result<Self, Error> {
+        let mut ids = match std::fs::read_dir(folder.join(PROFILE_FOLDER)) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+                .filter_map(|name| {
+                    let id = name.strip_suffix(".cbor").or_else(|| name.strip_suffix(".cbor.bak"))?;
+                    id.parse::<u32>().ok()
+                })
+                .collect::<Vec<_>>(),
+            Err(error) if error.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(error) => return Err(error.into()),
+        };
+        ids.sort_unstable();
+        ids.dedup();
+
+        let mut profiles = Self {
+            profiles: ids.iter().map(|&id| Profile { id, name: format!("Profile {id}") }).collect(),
+            last: None,
+            next_id: ids.last().map_or(1, |id| id + 1),
+            folder: folder.to_owned(),
+        };
+        profiles.store()?;
+        profiles.adopt_legacy_save()?;
+        Ok(profiles)
+    }
+
+    /// Creates a default profile if there is none, which takes over the save of versions without profiles.
+    fn adopt_legacy_save(&mut self) -> Result<(), Error> {
+        if !self.profiles.is_empty() {
+            return Ok(());
+        }
+        let profile = self.create(DEFAULT_NAME)?;
+        let legacy = self.folder.join(LEGACY_SAVE);
+        if legacy.exists() {
+            save::rename(&legacy, &self.save_file(&profile))?;
+        }
+        Ok(())
+    }
+
+    /// Returns all profiles in the order they were created.
+    pub fn profiles(&self) -> &[Profile] {
+        &self.profiles
+    }
+
+    /// Returns the profile with the id `id`.
+    pub fn get(&self, id: u32) -> Option<&Profile> {
+        self.profiles.iter().find(|profile| profile.id == id)
+    }
+
+    /// Returns the profile that was used last, or the first one.
+    pub fn last(&self) -> &Profile {
+        self.last.and_then(|id| self.get(id))
+            .or(self.profiles.first())
+            .expect("There always is a profile")
+    }
+
+    /// Remembers the profile `id` as the one that was used last.
+    pub fn set_last(&mut self, id: u32) -> Result<(), Error> {
+        self.reload()?;
+        self.get(id).ok_or(Error::UnknownProfileError(id))?;
+        self.last = Some(id);
+        self.store()
+    }
+
+    /// Returns where the progress of `profile` is saved.
+    pub fn save_file(&self, profile: &Profile) -> PathBuf {
+        self.folder.join(PROFILE_FOLDER).join(format!("{}.cbor", profile.id))
+    }
+
+    /// Adds a profile called `name`, which must not be taken yet.
+    pub fn create(&mut self, name: &str) -> Result<Profile, Error> {
+        self.reload()?;
+        let name = validate_name(name, &self.profiles, None)?;
+        let profile = Profile {
+            id: self.next_id,
+            name,
+        };
+        self.next_id += 1;
+        self.profiles.push(profile.clone());
+        self.store()?;
+        Ok(profile)
+    }
+
+    /// Renames the profile `id` to `name`, which must not be taken by another profile.
+    pub fn rename(&mut self, id: u32, name: &str) -> Result<(), Error> {
+        self.reload()?;
+        let name = validate_name(name, &self.profiles, Some(id))?;
+        let profile = self.profiles.iter_mut()
+            .find(|profile| profile.id == id)
+            .ok_or(Error::UnknownProfileError(id))?;
+        profile.name = name;
+        self.store()
+    }
+
+    /// Removes the profile together with its progress. Deleting the last profile creates a new default one.
+    pub fn delete(&mut self, id: u32) -> Result<(), Error> {
+        self.reload()?;
+        let idx = self.profiles.iter()
+            .position(|profile| profile.id == id)
+            .ok_or(Error::UnknownProfileError(id))?;
+        let profile = self.profiles.remove(idx);
+        if self.last == Some(id) {
+            self.last = None;
+        }
+        self.store()?;
+        save::remove(&self.save_file(&profile))?;
+
+        if self.profiles.is_empty() {
+            self.create(DEFAULT_NAME)?;
+        }
+        Ok(())
+    }
+
+    /// Takes over the changes another session wrote to the folder, so storing does not undo them.
+    fn reload(&mut self) -> Result<(), Error> {
+        let stored: Self = match std::fs::read_to_string(self.folder.join(INDEX)) {
+            Ok(text) => toml::from_str(&text)?,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(error) => return Err(error.into()),
+        };
+        self.profiles = stored.profiles;
+        self.last = stored.last;
+        // ids are never taken twice, even if the other session had not seen ours
+        self.next_id = self.next_id.max(stored.next_id);
+        Ok(())
+    }
+
+    fn store(&self) -> Result<(), Error> {
+        std::fs::create_dir_all(&self.folder)?;
+        let path = self.folder.join(INDEX);
+        let temporary = path.with_extension("toml.tmp");
+        std::fs::write(&temporary, toml::to_string(self)?)?;
+        std::fs::rename(&temporary, &path)?;
+        Ok(())
+    }
+}
+
+/// Returns the trimmed name if no other profile than `id` in `profiles` is called like it.
+pub fn validate_name(name: &str, profiles: &[Profile], id: Option<u32>) -> Result<String, Error> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(Error::EmptyNameError);
+    }
+    let taken = profiles.iter()
+        .any(|profile| Some(profile.id) != id && profile.name.to_lowercase() == name.to_lowercase());
+    if taken {
+        return Err(Error::DuplicateNameError(name.to_owned()));
+    }
+    Ok(name.to_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Trainer;
+    use super::*;
+
+    fn names(profiles: &Profiles) -> Vec<&str> {
+        profiles.profiles().iter().map(|profile| profile.name.as_str()).collect()
+    }
+
+    #[test]
+    fn default_profile() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+
+        let profiles = Profiles::load(dir.path()).expect("readable profiles");
+
+        assert_eq!(names(&profiles), vec![DEFAULT_NAME], "expect a default profile");
+        assert_eq!(profiles.last().name, DEFAULT_NAME, "expect the default profile to be used");
+    }
+
+    #[test]
+    fn adopt_legacy_save() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let legacy = dir.path().join(LEGACY_SAVE);
+        std::fs::write(&legacy, save::encode(&Trainer::default()).expect("encodable trainer")).expect("writable save");
+
+        let profiles = Profiles::load(dir.path()).expect("readable profiles");
+
+        assert!(!legacy.exists(), "expect the old save to be moved");
+        assert!(matches!(save::restore(&profiles.save_file(profiles.last())), save::Restored::Save(_)), "expect the default profile to take over the old save");
+    }
+
+    #[test]
+    fn create_rename_and_reload() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let mut profiles = Profiles::load(dir.path()).expect("readable profiles");
+
+        let anna = profiles.create(" Anna ").expect("new profile");
+        profiles.rename(anna.id, "Anne").expect("renamed profile");
+        profiles.set_last(anna.id).expect("existing profile");
+
+        let reloaded = Profiles::load(dir.path()).expect("readable profiles");
+        assert_eq!(names(&reloaded), vec![DEFAULT_NAME, "Anne"], "expect the profiles to be stored");
+        assert_eq!(reloaded.last(), &Profile { id: anna.id, name: "Anne".to_owned() }, "expect the last profile to be stored");
+        assert_eq!(reloaded, profiles, "expect nothing else to change");
+    }
+
+    #[test]
+    fn reject_invalid_names() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let mut profiles = Profiles::load(dir.path()).expect("readable profiles");
+        let anna = profiles.create("Anna").expect("new profile");
+
+        assert!(matches!(profiles.create("  "), Err(Error::EmptyNameError)), "expect an empty name to be rejected");
+        assert!(matches!(profiles.create("anna"), Err(Error::DuplicateNameError(_))), "expect a taken name to be rejected");
+        assert!(matches!(profiles.rename(anna.id, "default"), Err(Error::DuplicateNameError(_))), "expect a taken name to be rejected");
+        profiles.rename(anna.id, "ANNA").expect("a profile may keep its own name");
+        assert!(matches!(profiles.rename(99, "Ben"), Err(Error::UnknownProfileError(99))), "expect an unknown profile to be rejected");
+    }
+
+    #[test]
+    fn recover_damaged_profile_list() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let mut profiles = Profiles::load(dir.path()).expect("readable profiles");
+        let anna = profiles.create("Anna").expect("new profile");
+        profiles.create("Ben").expect("new profile");
+        save::write(&save::encode(&Trainer::default()).expect("encodable trainer"), &profiles.save_file(&anna)).expect("writable save");
+        std::fs::write(dir.path().join(INDEX), "not = [a profile list").expect("writable profile list");
+
+        assert!(matches!(Profiles::load(dir.path()), Err(Error::DeserializeError(_))), "expect the damaged list to be reported");
+        let mut recovered = Profiles::recover(dir.path()).expect("recovered profiles");
+
+        assert_eq!(recovered.profiles(), &[Profile { id: anna.id, name: format!("Profile {}", anna.id) }], "expect the profile with a save to be recovered");
+        let new = recovered.create("Ben").expect("new profile");
+        assert!(new.id > anna.id, "expect new profiles not to take over existing saves");
+        assert_eq!(Profiles::load(dir.path()).expect("readable profiles"), recovered, "expect the recovered list to be stored");
+    }
+
+    #[test]
+    fn keep_changes_of_other_session() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let mut first = Profiles::load(dir.path()).expect("readable profiles");
+        let mut second = Profiles::load(dir.path()).expect("readable profiles");
+
+        let anna = first.create("Anna").expect("new profile");
+        let ben = second.create("Ben").expect("new profile");
+
+        assert_ne!(anna.id, ben.id, "expect the sessions not to take the same id");
+        assert!(matches!(second.create("anna"), Err(Error::DuplicateNameError(_))), "expect the name of the other session to be taken");
+        let names = Profiles::load(dir.path()).expect("readable profiles").profiles().iter().map(|profile| profile.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names, vec![DEFAULT_NAME, "Anna", "Ben"], "expect the profiles of both sessions to be kept");
+    }
+
+    #[test]
+    fn separate_save_files() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let mut profiles = Profiles::load(dir.path()).expect("readable profiles");
+        let anna = profiles.create("Anna").expect("new profile");
+
+        assert_ne!(profiles.save_file(&anna), profiles.save_file(profiles.last()), "expect every profile to have its own save");
+    }
+
+    #[test]
+    fn delete_profile_and_progress() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let mut profiles = Profiles::load(dir.path()).expect("readable profiles");
+        let anna = profiles.create("Anna").expect("new profile");
+        profiles.set_last(anna.id).expect("existing profile");
+        let save_file = profiles.save_file(&anna);
+        save::write(&save::encode(&Trainer::default()).expect("encodable trainer"), &save_file).expect("writable save");
+
+        profiles.delete(anna.id).expect("deletable profile");
+
+        assert_eq!(names(&profiles), vec![DEFAULT_NAME], "expect the profile to be removed");
+        assert_eq!(profiles.last().name, DEFAULT_NAME, "expect another profile to be used");
+        assert!(!save_file.exists(), "expect the progress to be removed");
+    }
+
+    #[test]
+    fn delete_only_profile() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let mut profiles = Profiles::load(dir.path()).expect("readable profiles");
+        let id = profiles.last().id;
+
+        profiles.delete(id).expect("deletable profile");
+
+        assert_eq!(profiles.profiles().len(), 1, "expect a new profile to be created");
+        assert_ne!(profiles.last().id, id, "expect the new profile to start without progress");
+    }
+}
@@ -0,0 +1,413 @@
+//! Save files: the trainer of a profile written as versioned CBOR, with a backup of the previous save.
+
+use std::ffi::OsString;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use ciborium::Value;
+use serde::Serialize;
+use thiserror::Error;
+use crate::Trainer;
+
+/// The version of the save format written by this build.
+pub const VERSION: u32 = 2;
+
+/// Upgrades the trainer of a save by one version, `MIGRATIONS[v]` turns version `v` into `v + 1`.
+const MIGRATIONS: [fn(Value) -> Result<Value, Error>; VERSION as usize] = [
+    from_v0,
+    from_v1,
+];
+
+/// Reasons a save file cannot be read or written.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Reading or writing the file failed.
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    /// The trainer cannot be written.
+    #[error("failed to write the save file: {0}")]
+    SerializeError(#[from] ciborium::ser::Error<std::io::Error>),
+    /// The save file is damaged.
+    #[error("the save file is damaged: {0}")]
+    DeserializeError(#[from] ciborium::de::Error<std::io::Error>),
+    /// The save file was written by a newer version.
+    #[error("the save file was written by a newer version (format {0}, supported up to {VERSION})")]
+    UnsupportedVersionError(u32),
+}
+
+/// What is written to the save file: the trainer tagged with the format version.
+#[derive(Serialize)]
+struct Envelope<'a> {
+    version: u32,
+    trainer: &'a Trainer,
+}
+
+/// The outcome of reading a save file.
+#[derive(Debug)]
+pub enum Restored<E = Error> {
+    /// There is no save file yet.
+    Missing,
+    /// The save file was read.
+    Save(Trainer),
+    /// The save file could not be read, but its backup could.
+    Backup(Trainer, E),
+    /// Neither the save file nor its backup could be read.
+    Corrupted(E),
+}
+
+impl<E> Restored<E> {
+    /// Converts the error with `op`, keeping the trainer.
+    pub fn map_err<F>(self, op: impl FnOnce(E) -> F) -> Restored<F> {
+        match self {
+            Restored::Missing => Restored::Missing,
+            Restored::Save(trainer) => Restored::Save(trainer),
+            Restored::Backup(trainer, error) => Restored::Backup(trainer, op(error)),
+            Restored::Corrupted(error) => Restored::Corrupted(op(error)),
+        }
+    }
+}
+
+/// Writes `trainer` to `path`, see [write()].
+pub fn save(trainer: &Trainer, path: &Path) -> Result<(), Error> {
+    write(&encode(trainer)?, path)
+}
+
+/// Serializes `trainer` into the contents of a save file.
+pub fn encode(trainer: &Trainer) -> Result<Vec<u8>, Error> {
+    let mut data = Vec::new();
+    ciborium::into_writer(&Envelope { version: VERSION, trainer }, &mut data)?;
+    Ok(data)
+}
+
+/// Writes the encoded save `data` to `path` without ever leaving a half written save behind.
+///
+/// The data is written to a temporary file first, which then replaces the save file. The replaced save file
+/// is kept as a backup, unless it is damaged itself. A save of a newer version is kept as well, so running an
+/// older build does not destroy it.
+pub fn write(data: &[u8], path: &Path) -> Result<(), Error> {
+    if let Some(folder) = path.parent() {
+        std::fs::create_dir_all(folder)?;
+    }
+
+    let temporary = sibling(path, ".tmp");
+    let mut writer = BufWriter::new(File::create(&temporary)?);
+    writer.write_all(data)?;
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+    drop(writer);
+
+    if matches!(read(path), Ok(_) | Err(Error::UnsupportedVersionError(_))) {
+        std::fs::rename(path, backup(path))?;
+    }
+    std::fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+/// Reads the save file at `path`, falling back to its backup if it is damaged or missing.
+pub fn restore(path: &Path) -> Restored {
+    let error = match read(path) {
+        Ok(trainer) => return Restored::Save(trainer),
+        Err(error) => error,
+    };
+    let backup = backup(path);
+    match read(&backup) {
+        Ok(trainer) => Restored::Backup(trainer, error),
+        Err(_) if !path.exists() && !backup.exists() => Restored::Missing,
+        Err(_) => Restored::Corrupted(error),
+    }
+}
+
+/// Moves the save file at `from` together with its backup to `to`.
+pub fn rename(from: &Path, to: &Path) -> std::io::Result<()> {
+    if let Some(folder) = to.parent() {
+        std::fs::create_dir_all(folder)?;
+    }
+    std::fs::rename(from, to)?;
+    match std::fs::rename(backup(from), backup(to)) {
+        Err(error) if error.kind() != ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
+
+/// Deletes the save file at `path` together with its backup. Missing files are ignored.
+pub fn remove(path: &Path) -> std::io::Result<()> {
+    for path in [path.to_owned(), backup(path), sibling(path, ".tmp")] {
+        match std::fs::remove_file(path) {
+            Err(error) if error.kind() != ErrorKind::NotFound => return Err(error),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn backup(path: &Path) -> PathBuf {
+    sibling(path, ".bak")
+}
+
+fn read(path: &Path) -> Result<Trainer, Error> {
+    decode(ciborium::from_reader(BufReader::new(File::open(path)?))?)
+}
+
+/// Migrates a save of any known version to the current one and reads the trainer from it.
+fn decode(save: Value) -> Result<Trainer, Error> {
+    let (version, mut trainer) = split(save)?;
+    if version > VERSION {
+        return Err(Error::UnsupportedVersionError(version));
+    }
+    for migration in &MIGRATIONS[version as usize..] {
+        trainer = migration(trainer)?;
+    }
+    trainer.deserialized().map_err(damaged)
+}
+
+/// Splits a save into its version and trainer. Saves without an envelope are version 0.
+fn split(save: Value) -> Result<(u32, Value), Error> {
+    let Value::Map(mut entries) = save else {
+        return Ok((0, save));
+    };
+    let field = |name: &str| entries.iter().position(|(key, _)| key.as_text() == Some(name));
+    let (Some(version), Some(trainer)) = (field("version"), field("trainer")) else {
+        return Ok((0, Value::Map(entries)));
+    };
+    let version = entries[version].1.deserialized().map_err(damaged)?;
+    Ok((version, entries.swap_remove(trainer).1))
+}
+
+fn damaged(error: ciborium::value::Error) -> Error {
+    Error::DeserializeError(ciborium::de::Error::Semantic(None, error.to_string()))
+}
+
+/// Version 0 saves are the bare trainer. Everything added to it since has a default, so it is kept as it is.
+fn from_v0(trainer: Value) -> Result<Value, Error> {
+    Ok(trainer)
+}
+
+/// Version 1 counted the failed image loads on every word. Version 2 keeps the counts in the trainer, by spelling.
+fn from_v1(trainer: Value) -> Result<Value, Error> {
+    let Value::Map(mut entries) = trainer else {
+        return Ok(trainer);
+    };
+    let mut failures = Vec::new();
+    let words = entries.iter_mut().find(|(key, _)| key.as_text() == Some("words"));
+    if let Some((_, Value::Array(words))) = words {
+        for word in words {
+            let Value::Map(fields) = word else { continue };
+            let Some(count) = fields.iter().position(|(key, _)| key.as_text() == Some("image_failures")) else { continue };
+            let count = fields.remove(count).1;
+            let spelling = fields.iter().find(|(key, _)| key.as_text() == Some("word")).map(|(_, spelling)| spelling.clone());
+            if let (Some(spelling), Some(failed)) = (spelling, count.as_integer()) {
+                if failed != 0.into() {
+                    failures.push((spelling, count));
+                }
+            }
+        }
+    }
+    entries.push((Value::from("image_failures"), Value::Map(failures)));
+    Ok(Value::Map(entries))
+}
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+    use chrono::{TimeZone, Utc};
+    use crate::source::ImageSource;
+    use crate::Word;
+    use super::*;
+
+    fn trainer(spelling: &str) -> Trainer {
+        Trainer::new(vec![Word {
+            word: spelling.to_owned(),
+            alternatives: Vec::new(),
+            url: ImageSource::from_str("https://example.org/").expect("valid url"),
+            credits: String::new(),
+        }])
+    }
+
+    fn fixture(data: &[u8]) -> Result<Trainer, Error> {
+        decode(ciborium::from_reader(data)?)
+    }
+
+    /// The trainer stored in `fixtures/save_v1.cbor` and `fixtures/save_v2.cbor`.
+    fn v1_trainer() -> Trainer {
+        let time = Utc.with_ymd_and_hms(2024, 3, 1, 8, 0, 0).unwrap();
+        let mut trainer = Trainer::new(vec![
+            Word {
+                word: "apple".to_owned(),
+                alternatives: Vec::new(),
+                url: ImageSource::from_str("https://example.org/apple.png").expect("valid url"),
+                credits: "apple by someone".to_owned(),
+            },
+            Word {
+                word: "dog".to_owned(),
+                alternatives: vec!["puppy".to_owned()],
+                url: ImageSource::from_str("images/dog.png").expect("valid path"),
+                credits: "dog by someone".to_owned(),
+            },
+        ]);
+        trainer.record_image_failure("dog");
+        trainer.record_image_failure("dog");
+        trainer.set_directory(Some(PathBuf::from("/decks/animals")));
+        trainer.select_at(0, time);
+        trainer.guess_at("apple", time);
+        trainer.select_at(1, time);
+        trainer.guess_at("cat", time);
+        trainer
+    }
+
+    #[test]
+    fn migrate_v0_save() {
+        let trainer = fixture(include_bytes!("fixtures/save_v0.cbor")).expect("readable v0 save");
+
+        let words = trainer.words().iter().map(|word| word.word.as_str()).collect::<Vec<_>>();
+        assert_eq!(words, vec!["apple", "dog"], "expect the words to be kept");
+        assert_eq!(trainer.words()[0].url, ImageSource::from_str("https://example.org/apple.png").expect("valid url"), "expect the image url to be kept");
+        assert_eq!(trainer.words()[1].credits, "dog by someone", "expect the credits to be kept");
+        assert_eq!(trainer.selected().map(|word| word.word.as_str()), Some("dog"), "expect the selection to be kept");
+        assert_eq!((trainer.statistic().correct(), trainer.statistic().incorrect()), (3, 1), "expect the statistic to be kept");
+        assert!(trainer.history().is_empty(), "expect an empty history");
+    }
+
+    #[test]
+    fn migrate_v1_save() {
+        let trainer = fixture(include_bytes!("fixtures/save_v1.cbor")).expect("readable v1 save");
+
+        assert_eq!(trainer, v1_trainer(), "expect the whole trainer to be read");
+        assert_eq!(trainer.image_failures("dog"), 2, "expect the image failures to be moved to the trainer");
+    }
+
+    #[test]
+    fn read_v2_save() {
+        let trainer = fixture(include_bytes!("fixtures/save_v2.cbor")).expect("readable v2 save");
+
+        assert_eq!(trainer, v1_trainer(), "expect the whole trainer to be read");
+    }
+
+    #[test]
+    fn migrated_save_is_written_in_current_version() {
+        let (_dir, path) = save_path();
+        std::fs::write(&path, include_bytes!("fixtures/save_v0.cbor")).expect("writable save");
+        let Restored::Save(trainer) = restore(&path) else { panic!("expect the v0 save to be restored") };
+
+        save(&trainer, &path).expect("writable save");
+
+        let save: Value = ciborium::from_reader(File::open(&path).expect("readable save")).expect("valid cbor");
+        assert!(matches!(split(save), Ok((VERSION, _))), "expect the current version to be written");
+        assert!(matches!(restore(&path), Restored::Save(restored) if restored == trainer), "expect the migrated trainer");
+    }
+
+    #[test]
+    fn reject_newer_version() {
+        let (_dir, path) = save_path();
+        let newer = Value::Map(vec![
+            (Value::from("version"), Value::from(VERSION + 1)),
+            (Value::from("trainer"), Value::Map(Vec::new())),
+        ]);
+        ciborium::into_writer(&newer, File::create(&path).expect("writable save")).expect("serializable save");
+
+        assert!(matches!(restore(&path), Restored::Corrupted(Error::UnsupportedVersionError(version)) if version == VERSION + 1), "expect a newer save to be rejected");
+        save(&trainer("apple"), &path).expect("writable save");
+        assert!(matches!(read(&backup(&path)), Err(Error::UnsupportedVersionError(_))), "expect the newer save to be kept as backup");
+    }
+
+    fn save_path() -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let path = dir.path().join("save.cbor");
+        (dir, path)
+    }
+
+    #[test]
+    fn missing_save() {
+        let (_dir, path) = save_path();
+
+        assert!(matches!(restore(&path), Restored::Missing), "expect no save to be found");
+    }
+
+    #[test]
+    fn save_and_restore() {
+        let (_dir, path) = save_path();
+
+        save(&trainer("apple"), &path).expect("writable save");
+
+        assert!(matches!(restore(&path), Restored::Save(restored) if restored == trainer("apple")), "expect the saved trainer");
+        assert!(!sibling(&path, ".tmp").exists(), "expect no temporary file to be left behind");
+    }
+
+    #[test]
+    fn keep_previous_save_as_backup() {
+        let (_dir, path) = save_path();
+
+        save(&trainer("apple"), &path).expect("writable save");
+        save(&trainer("dog"), &path).expect("writable save");
+
+        assert!(matches!(read(&backup(&path)), Ok(restored) if restored == trainer("apple")), "expect the previous save as backup");
+    }
+
+    #[test]
+    fn restore_backup_of_truncated_save() {
+        let (_dir, path) = save_path();
+        save(&trainer("apple"), &path).expect("writable save");
+        save(&trainer("dog"), &path).expect("writable save");
+        let data = std::fs::read(&path).expect("readable save");
+        std::fs::write(&path, &data[..data.len() / 2]).expect("writable save");
+
+        assert!(matches!(restore(&path), Restored::Backup(restored, Error::DeserializeError(_)) if restored == trainer("apple")), "expect the backup to be restored");
+    }
+
+    #[test]
+    fn restore_backup_of_missing_save() {
+        let (_dir, path) = save_path();
+        save(&trainer("apple"), &path).expect("writable save");
+        save(&trainer("dog"), &path).expect("writable save");
+        std::fs::remove_file(&path).expect("removable save");
+
+        assert!(matches!(restore(&path), Restored::Backup(restored, Error::IoError(_)) if restored == trainer("apple")), "expect the backup to be restored");
+    }
+
+    #[test]
+    fn corrupted_save_without_backup() {
+        let (_dir, path) = save_path();
+        std::fs::write(&path, b"not a save file").expect("writable save");
+
+        assert!(matches!(restore(&path), Restored::Corrupted(Error::DeserializeError(_))), "expect the save to be reported as corrupted");
+    }
+
+    #[test]
+    fn corrupted_save_and_backup() {
+        let (_dir, path) = save_path();
+        std::fs::write(&path, b"not a save file").expect("writable save");
+        std::fs::write(backup(&path), b"").expect("writable backup");
+
+        assert!(matches!(restore(&path), Restored::Corrupted(_)), "expect the save to be reported as corrupted");
+    }
+
+    #[test]
+    fn keep_backup_when_replacing_corrupted_save() {
+        let (_dir, path) = save_path();
+        save(&trainer("apple"), &path).expect("writable save");
+        save(&trainer("dog"), &path).expect("writable save");
+        std::fs::write(&path, b"not a save file").expect("writable save");
+
+        save(&trainer("cat"), &path).expect("writable save");
+
+        assert!(matches!(read(&backup(&path)), Ok(restored) if restored == trainer("apple")), "expect the last good backup to be kept");
+        assert!(matches!(restore(&path), Restored::Save(restored) if restored == trainer("cat")), "expect the new save");
+    }
+
+    #[test]
+    fn ignore_interrupted_write() {
+        let (_dir, path) = save_path();
+        save(&trainer("apple"), &path).expect("writable save");
+        std::fs::write(sibling(&path, ".tmp"), b"half a sa").expect("writable temporary file");
+
+        assert!(matches!(restore(&path), Restored::Save(restored) if restored == trainer("apple")), "expect the complete save to be restored");
+        save(&trainer("dog"), &path).expect("writable save");
+        assert!(matches!(restore(&path), Restored::Save(restored) if restored == trainer("dog")), "expect the interrupted write to be replaced");
+    }
+}
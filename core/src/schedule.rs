@@ -0,0 +1,177 @@
+use std::fmt::Debug;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Leitner box intervals in seconds. A word in box `n` is due again `INTERVALS[n]` after it was last
+/// answered correctly.
+const INTERVALS: [i64; 5] = [0, 10 * 60, 24 * 60 * 60, 3 * 24 * 60 * 60, 7 * 24 * 60 * 60];
+
+#[derive(Default, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Review {
+    level: usize,
+    due: Option<DateTime<Utc>>,
+}
+
+impl Review {
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    pub fn due(&self) -> Option<DateTime<Utc>> {
+        self.due
+    }
+
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.due.is_none() || self.due <= Some(now)
+    }
+
+    pub fn promote(&mut self, now: DateTime<Utc>) {
+        self.level = (self.level + 1).min(INTERVALS.len() - 1);
+        self.due = Some(now + Duration::seconds(INTERVALS[self.level]));
+    }
+
+    pub fn demote(&mut self, now: DateTime<Utc>) {
+        self.level = 0;
+        self.due = Some(now);
+    }
+}
+
+#[derive(Default, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Schedule {
+    reviews: Vec<Review>,
+}
+
+impl Schedule {
+    pub fn review(&self, idx: usize) -> Review {
+        self.reviews.get(idx).cloned().unwrap_or_default()
+    }
+
+    pub fn review_mut(&mut self, idx: usize) -> &mut Review {
+        if self.reviews.len() <= idx {
+            self.reviews.resize(idx + 1, Review::default());
+        }
+        &mut self.reviews[idx]
+    }
+
+    /// Reorders the reviews for a new word list. `mapping[new]` is the old index of the word at `new`.
+    pub fn remap(&mut self, mapping: &[Option<usize>]) {
+        self.reviews = mapping.iter()
+            .map(|old| old.map(|old| self.review(old)).unwrap_or_default())
+            .collect();
+    }
+
+    /// Returns the indices of the words out of `count` that should be asked next.
+    ///
+    /// Due words in the lowest box are preferred. If no word is due, the words that become due first
+    /// are returned. `exclude` is skipped unless it is the only word.
+    pub fn candidates(&self, count: usize, now: DateTime<Utc>, exclude: Option<usize>) -> Vec<usize> {
+        let indices = (0..count)
+            .filter(|&idx| count == 1 || Some(idx) != exclude)
+            .collect::<Vec<_>>();
+        let due = indices.iter()
+            .copied()
+            .filter(|&idx| self.review(idx).is_due(now))
+            .collect::<Vec<_>>();
+
+        if due.is_empty() {
+            let earliest = indices.iter().filter_map(|&idx| self.review(idx).due()).min();
+            indices.into_iter()
+                .filter(|&idx| self.review(idx).due() == earliest)
+                .collect()
+        } else {
+            let lowest = due.iter().map(|&idx| self.review(idx).level()).min();
+            due.into_iter()
+                .filter(|&idx| Some(self.review(idx).level()) == lowest)
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, 8, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn new_review_is_due() {
+        let review = Review::default();
+
+        assert_eq!(review.level(), 0, "expect a new word to start in the first box");
+        assert!(review.is_due(now()), "expect a new word to be due immediately");
+    }
+
+    #[test]
+    fn promote_review() {
+        let mut review = Review::default();
+
+        review.promote(now());
+
+        assert_eq!(review.level(), 1, "expect the word to move up one box");
+        assert_eq!(review.due(), Some(now() + Duration::minutes(10)), "expect the word to be due after the box interval");
+        assert!(!review.is_due(now()), "expect the word not to be due right away");
+    }
+
+    #[test]
+    fn promote_review_beyond_last_box() {
+        let mut review = Review::default();
+
+        for _ in 0..10 {
+            review.promote(now());
+        }
+
+        assert_eq!(review.level(), INTERVALS.len() - 1, "expect the word to stay in the last box");
+        assert_eq!(review.due(), Some(now() + Duration::days(7)), "expect the word to be due after the last interval");
+    }
+
+    #[test]
+    fn demote_review() {
+        let mut review = Review::default();
+        review.promote(now());
+        review.promote(now());
+
+        review.demote(now());
+
+        assert_eq!(review.level(), 0, "expect the word to fall back to the first box");
+        assert!(review.is_due(now()), "expect the word to be due immediately");
+    }
+
+    #[test]
+    fn candidates_from_empty_wordlist() {
+        let schedule = Schedule::default();
+
+        assert!(schedule.candidates(0, now(), None).is_empty(), "expect no candidates");
+    }
+
+    #[test]
+    fn candidates_prefer_lowest_due_box() {
+        let mut schedule = Schedule::default();
+        schedule.review_mut(0).promote(now() - Duration::days(2));
+        schedule.review_mut(1).promote(now() - Duration::days(2));
+        schedule.review_mut(1).promote(now() - Duration::days(2));
+        schedule.review_mut(2).promote(now());
+
+        assert_eq!(schedule.candidates(3, now(), None), vec![0], "expect the due word in the lowest box");
+    }
+
+    #[test]
+    fn candidates_without_due_words() {
+        let mut schedule = Schedule::default();
+        schedule.review_mut(0).promote(now());
+        schedule.review_mut(0).promote(now());
+        schedule.review_mut(1).promote(now());
+
+        assert_eq!(schedule.candidates(2, now(), None), vec![1], "expect the word that becomes due first");
+    }
+
+    #[test]
+    fn candidates_exclude_previous_word() {
+        let schedule = Schedule::default();
+
+        assert_eq!(schedule.candidates(2, now(), Some(0)), vec![1], "expect the previous word to be skipped");
+        assert_eq!(schedule.candidates(1, now(), Some(0)), vec![0], "expect the only word to be asked again");
+    }
+}
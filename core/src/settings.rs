@@ -0,0 +1,178 @@
+//! The settings file shared by every frontend.
+
+use std::fmt::Debug;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use directories::ProjectDirs;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+lazy_static!(
+    /// Where every frontend looks for the settings.
+    pub static ref SETTINGS_FILE: PathBuf = ProjectDirs::from("at.ac", "tgm", "spelling_trainer").expect("Failed to get project dirs").config_dir().join("settings.toml");
+);
+
+/// Reasons the settings cannot be read.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Reading the file failed.
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    /// The file is not valid.
+    #[error(transparent)]
+    TomlError(#[from] toml::de::Error),
+}
+
+/// The user editable settings file.
+#[derive(Default, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// How images are downloaded.
+    pub network: NetworkSettings,
+    /// Where the progress is kept.
+    pub storage: StorageSettings,
+}
+
+impl Settings {
+    /// Reads the settings from `path`. A missing file results in the default settings.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Ok(toml::from_str(&text)?),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+/// How images are downloaded.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    /// How long to wait for a connection to the server.
+    #[serde(with = "humantime_serde")]
+    pub connect_timeout: Duration,
+    /// How long to wait for the response headers and for every chunk of the body.
+    #[serde(with = "humantime_serde")]
+    pub read_timeout: Duration,
+    /// How long a single attempt to download an image may take at most, including the whole body.
+    #[serde(with = "humantime_serde")]
+    pub total_timeout: Duration,
+    /// How often a failed download is repeated.
+    pub retries: u32,
+    /// The delay before the first retry, doubled for every further retry.
+    #[serde(with = "humantime_serde")]
+    pub backoff: Duration,
+    /// The longest delay between two retries.
+    #[serde(with = "humantime_serde")]
+    pub max_backoff: Duration,
+    /// The maximum size of an image in bytes.
+    pub max_body_size: u64,
+    /// An `http://`, `https://` or `socks5://` proxy all requests are sent through.
+    pub proxy: Option<String>,
+    /// PEM files with root certificates trusted in addition to the system ones, e.g. of an intercepting proxy.
+    pub ca_certificates: Vec<PathBuf>,
+    /// How requests identify the application.
+    pub user_agent: String,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(10),
+            read_timeout: Duration::from_secs(30),
+            total_timeout: Duration::from_secs(120),
+            retries: 3,
+            backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            max_body_size: 20 * 1024 * 1024,
+            proxy: None,
+            ca_certificates: Vec::new(),
+            user_agent: concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")).to_owned(),
+        }
+    }
+}
+
+/// Where profiles and their progress are kept.
+#[derive(Default, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageSettings {
+    /// How the profiles and their progress are stored.
+    pub backend: Backend,
+    /// The database of the SQLite backend. By default it is kept next to the save files.
+    pub database: Option<PathBuf>,
+}
+
+/// The ways the progress can be stored.
+#[derive(Default, Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// A CBOR save file for every profile.
+    #[default]
+    Cbor,
+    /// One SQLite database for all profiles.
+    Sqlite,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn missing_file() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+
+        let settings = Settings::load(&dir.path().join("settings.toml")).expect("default settings");
+
+        assert_eq!(settings, Settings::default(), "expect the default settings");
+    }
+
+    #[test]
+    fn load_network_settings() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let path = dir.path().join("settings.toml");
+        std::fs::write(&path, r#"
+            [network]
+            connect_timeout = "5s"
+            backoff = "250ms"
+            proxy = "socks5://proxy.school:1080"
+            ca_certificates = ["/etc/school/ca.pem"]
+            user_agent = "classroom"
+        "#).expect("writable settings");
+
+        let network = Settings::load(&path).expect("valid settings").network;
+
+        assert_eq!(network.connect_timeout, Duration::from_secs(5), "expect the timeout to be read");
+        assert_eq!(network.backoff, Duration::from_millis(250), "expect the backoff to be read");
+        assert_eq!(network.proxy.as_deref(), Some("socks5://proxy.school:1080"), "expect the proxy to be read");
+        assert_eq!(network.ca_certificates, vec![PathBuf::from("/etc/school/ca.pem")], "expect the certificates to be read");
+        assert_eq!(network.user_agent, "classroom", "expect the user agent to be read");
+        assert_eq!(network.retries, NetworkSettings::default().retries, "expect missing settings to keep their default");
+    }
+
+    #[test]
+    fn load_storage_settings() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let path = dir.path().join("settings.toml");
+        std::fs::write(&path, r#"
+            [storage]
+            backend = "sqlite"
+            database = "/srv/classroom/progress.sqlite"
+        "#).expect("writable settings");
+
+        let storage = Settings::load(&path).expect("valid settings").storage;
+
+        assert_eq!(storage.backend, Backend::Sqlite, "expect the backend to be read");
+        assert_eq!(storage.database, Some(PathBuf::from("/srv/classroom/progress.sqlite")), "expect the database to be read");
+    }
+
+    #[test]
+    fn invalid_settings() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let path = dir.path().join("settings.toml");
+        std::fs::write(&path, "[network]\nretries = \"many\"\n").expect("writable settings");
+
+        assert!(matches!(Settings::load(&path), Err(Error::TomlError(_))), "expect invalid settings to be rejected");
+    }
+}
@@ -0,0 +1,199 @@
+//! Where the images of the words come from.
+
+use std::fmt::{Debug, Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use data_url::DataUrl;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+use url::Url;
+
+/// Reasons an image source cannot be read.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// There is no source at all.
+    #[error("the image source is empty")]
+    EmptyError,
+    /// The source looks like a URL but is not a valid one.
+    #[error("invalid url \"{0}\": {1}")]
+    UrlError(String, url::ParseError),
+    /// The URL scheme is not one of the supported ones.
+    #[error("unsupported url scheme \"{0}\", expected http, https, file or data")]
+    UnsupportedSchemeError(String),
+    /// The `file://` URL does not point at a local path.
+    #[error("invalid file url {0}")]
+    FileUrlError(Url),
+    /// The `data:` URL cannot be decoded.
+    #[error("invalid data url: {0}")]
+    DataUrlError(String),
+}
+
+/// Where the image of a word comes from.
+///
+/// Sources are written as strings: `http(s)://` and `data:` URLs are kept as they are, `file://` URLs and absolute
+/// paths point at a file on disk and anything else is a path relative to the directory of the word list.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ImageSource {
+    /// An image on the web.
+    Web(Url),
+    /// An image file on disk.
+    File(PathBuf),
+    /// An image file relative to the directory of the word list.
+    Relative(PathBuf),
+    /// An image embedded in a `data:` URL.
+    Data(Url),
+}
+
+impl ImageSource {
+    /// Turns a relative path into a file inside `directory`. Other sources are returned unchanged.
+    pub fn resolve(&self, directory: Option<&Path>) -> Self {
+        match (self, directory) {
+            (ImageSource::Relative(path), Some(directory)) => ImageSource::File(directory.join(path)),
+            (source, _) => source.clone(),
+        }
+    }
+
+    /// Returns the file extension of the image, or the subtype of the mime type for data URLs.
+    pub fn extension(&self) -> Option<String> {
+        match self {
+            ImageSource::Web(url) => Path::new(url.path()).extension()?.to_str().map(str::to_owned),
+            ImageSource::File(path) | ImageSource::Relative(path) => path.extension()?.to_str().map(str::to_owned),
+            ImageSource::Data(url) => DataUrl::process(url.as_str()).ok()
+                .map(|data| data.mime_type().subtype.clone()),
+        }
+    }
+}
+
+/// Decodes the body of a `data:` URL.
+pub fn decode(url: &Url) -> Result<Vec<u8>, Error> {
+    let data = DataUrl::process(url.as_str()).map_err(|error| Error::DataUrlError(format!("{error:?}")))?;
+    let (body, _) = data.decode_to_vec().map_err(|error| Error::DataUrlError(format!("{error:?}")))?;
+    Ok(body)
+}
+
+impl FromStr for ImageSource {
+    type Err = Error;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let source = source.trim();
+        if source.is_empty() {
+            return Err(Error::EmptyError);
+        }
+        if Path::new(source).is_absolute() {
+            return Ok(ImageSource::File(PathBuf::from(source)));
+        }
+
+        match Url::parse(source) {
+            Ok(url) => match url.scheme() {
+                "http" | "https" => Ok(ImageSource::Web(url)),
+                "file" => url.to_file_path()
+                    .map(ImageSource::File)
+                    .map_err(|_| Error::FileUrlError(url)),
+                "data" => {
+                    decode(&url)?;
+                    Ok(ImageSource::Data(url))
+                }
+                scheme => Err(Error::UnsupportedSchemeError(scheme.to_owned())),
+            },
+            Err(url::ParseError::RelativeUrlWithoutBase) => Ok(ImageSource::Relative(PathBuf::from(source))),
+            Err(error) => Err(Error::UrlError(source.to_owned(), error)),
+        }
+    }
+}
+
+impl Display for ImageSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageSource::Web(url) | ImageSource::Data(url) => write!(f, "{url}"),
+            ImageSource::File(path) => match Url::from_file_path(path) {
+                Ok(url) => write!(f, "{url}"),
+                Err(_) => write!(f, "{}", path.display()),
+            },
+            ImageSource::Relative(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+impl From<Url> for ImageSource {
+    fn from(url: Url) -> Self {
+        match url.scheme() {
+            "file" => url.to_file_path().map_or(ImageSource::Web(url), ImageSource::File),
+            "data" => ImageSource::Data(url),
+            _ => ImageSource::Web(url),
+        }
+    }
+}
+
+impl Serialize for ImageSource {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ImageSource {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn source(source: &str) -> ImageSource {
+        ImageSource::from_str(source).expect("valid image source")
+    }
+
+    #[test]
+    fn parse_web_url() {
+        assert!(matches!(source("https://example.org/dog.png"), ImageSource::Web(_)), "expect an https url to be loaded from the web");
+        assert!(matches!(source("http://example.org/dog.png"), ImageSource::Web(_)), "expect an http url to be loaded from the web");
+    }
+
+    #[test]
+    fn parse_file() {
+        assert_eq!(source("file:///images/dog.png"), ImageSource::File(PathBuf::from("/images/dog.png")), "expect a file url to point at the file");
+        assert_eq!(source("/images/dog.png"), ImageSource::File(PathBuf::from("/images/dog.png")), "expect an absolute path to point at the file");
+    }
+
+    #[test]
+    fn parse_relative_path() {
+        assert_eq!(source("images/dog.png"), ImageSource::Relative(PathBuf::from("images/dog.png")), "expect a relative path");
+    }
+
+    #[test]
+    fn parse_data_url() {
+        let source = source("data:image/png;base64,AQID");
+
+        assert!(matches!(&source, ImageSource::Data(_)), "expect a data url");
+        assert_eq!(source.extension(), Some("png".to_owned()), "expect the extension from the mime type");
+        let ImageSource::Data(url) = source else { unreachable!() };
+        assert_eq!(decode(&url).expect("valid data url"), vec![1, 2, 3], "expect the base64 body to be decoded");
+    }
+
+    #[test]
+    fn parse_invalid_sources() {
+        assert!(matches!(ImageSource::from_str(" "), Err(Error::EmptyError)), "expect an empty source to be rejected");
+        assert!(matches!(ImageSource::from_str("ftp://example.org/dog.png"), Err(Error::UnsupportedSchemeError(_))), "expect an unknown scheme to be rejected");
+        assert!(matches!(ImageSource::from_str("http://"), Err(Error::UrlError(..))), "expect an invalid url to be rejected");
+        assert!(matches!(ImageSource::from_str("data:image/png;base64,!!"), Err(Error::DataUrlError(_))), "expect an invalid data url to be rejected");
+    }
+
+    #[test]
+    fn resolve_relative_path() {
+        let directory = Path::new("/decks/animals");
+
+        assert_eq!(source("images/dog.png").resolve(Some(directory)), ImageSource::File(PathBuf::from("/decks/animals/images/dog.png")), "expect the path to be resolved against the directory");
+        assert_eq!(source("https://example.org/").resolve(Some(directory)), source("https://example.org/"), "expect a web url to stay unchanged");
+        assert_eq!(source("images/dog.png").resolve(None), source("images/dog.png"), "expect the path to stay relative without a directory");
+    }
+
+    #[test]
+    fn display_round_trip() {
+        for text in ["https://example.org/dog.png", "file:///images/dog.png", "images/dog.png", "data:image/png;base64,AQID"] {
+            assert_eq!(source(text).to_string(), text, "expect the source to be written as it was read");
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! How well a learner did, overall and on every word.
+
+use std::fmt::Debug;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Counts the guesses and hints of a learner, overall and for every word.
+#[derive(Default, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Statistic {
+    correct: usize,
+    incorrect: usize,
+    #[serde(default)]
+    near_misses: usize,
+    #[serde(default)]
+    hints: usize,
+    #[serde(default)]
+    hinted: usize,
+    #[serde(default)]
+    words: Vec<WordStatistic>,
+}
+
+/// The progress on a single word.
+#[derive(Default, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct WordStatistic {
+    attempts: usize,
+    first_try: usize,
+    streak: usize,
+    last_seen: Option<DateTime<Utc>>,
+    #[serde(default)]
+    hints: usize,
+}
+
+impl Statistic {
+    /// Returns the number of checked guesses.
+    pub fn total(&self) -> usize {
+        self.correct + self.incorrect
+    }
+
+    /// Returns the number of guesses that matched a spelling of the word.
+    pub fn correct(&self) -> usize {
+        self.correct
+    }
+
+    /// Returns the number of guesses that did not match, near misses included.
+    pub fn incorrect(&self) -> usize {
+        self.incorrect
+    }
+
+    /// Incorrect guesses that were only a few letters off. These are also counted as incorrect.
+    pub fn near_misses(&self) -> usize {
+        self.near_misses
+    }
+
+    /// Returns the number of revealed hints.
+    pub fn hints(&self) -> usize {
+        self.hints
+    }
+
+    /// Correct guesses that were made after using at least one hint.
+    pub fn hinted(&self) -> usize {
+        self.hinted
+    }
+
+    /// Counts a correct guess.
+    pub fn increment_correct(&mut self) {
+        self.correct += 1;
+    }
+
+    /// Counts an incorrect guess that was not close.
+    pub fn increment_incorrect(&mut self) {
+        self.incorrect += 1;
+    }
+
+    /// Counts an incorrect guess that was only a few letters off.
+    pub fn increment_near_miss(&mut self) {
+        self.incorrect += 1;
+        self.near_misses += 1;
+    }
+
+    /// Counts a revealed hint.
+    pub fn increment_hints(&mut self) {
+        self.hints += 1;
+    }
+
+    /// Counts a correct guess that needed hints.
+    pub fn increment_hinted(&mut self) {
+        self.hinted += 1;
+    }
+
+    /// Returns the progress on the word at `idx`, which is empty for words that were never asked.
+    pub fn word(&self, idx: usize) -> WordStatistic {
+        self.words.get(idx).cloned().unwrap_or_default()
+    }
+
+    /// Reorders the word statistics for a new word list. `mapping[new]` is the old index of the word at `new`.
+    pub fn remap_words(&mut self, mapping: &[Option<usize>]) {
+        self.words = mapping.iter()
+            .map(|old| old.map(|old| self.word(old)).unwrap_or_default())
+            .collect();
+    }
+
+    /// Returns the progress on the word at `idx` for recording guesses.
+    pub fn word_mut(&mut self, idx: usize) -> &mut WordStatistic {
+        if self.words.len() <= idx {
+            self.words.resize(idx + 1, WordStatistic::default());
+        }
+        &mut self.words[idx]
+    }
+}
+
+impl WordStatistic {
+    /// Returns how often the word was guessed.
+    pub fn attempts(&self) -> usize {
+        self.attempts
+    }
+
+    /// Returns how often the word was spelled correctly without a wrong guess or a hint before.
+    pub fn first_try(&self) -> usize {
+        self.first_try
+    }
+
+    /// Returns how often in a row the word was spelled correctly on the first try.
+    pub fn streak(&self) -> usize {
+        self.streak
+    }
+
+    /// Returns when the word was last guessed.
+    pub fn last_seen(&self) -> Option<DateTime<Utc>> {
+        self.last_seen
+    }
+
+    /// Returns the number of hints revealed for the word.
+    pub fn hints(&self) -> usize {
+        self.hints
+    }
+
+    /// Counts a hint revealed for the word.
+    pub fn record_hint(&mut self) {
+        self.hints += 1;
+    }
+
+    /// Counts a guess. `first_try` tells whether it was made without a wrong guess or hint before.
+    pub fn record(&mut self, correct: bool, first_try: bool, now: DateTime<Utc>) {
+        self.attempts += 1;
+        self.last_seen = Some(now);
+        if !correct {
+            self.streak = 0;
+        } else if first_try {
+            self.first_try += 1;
+            self.streak += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+    use super::*;
+
+    #[test]
+    fn no_guess() {
+        let statistic = Statistic::default();
+
+        assert_eq!(statistic.correct(), 0, "expect no correct guess to get counted");
+        assert_eq!(statistic.incorrect(), 0, "expect no incorrect guess to get counted");
+        assert_eq!(statistic.total(), 0, "expect no guess to get counted");
+    }
+
+    #[test]
+    fn correct_guess() {
+        let mut statistic = Statistic::default();
+
+        statistic.increment_correct();
+
+        assert_eq!(statistic.correct(), 1, "expect one correct guess to get counted");
+        assert_eq!(statistic.incorrect(), 0, "expect no incorrect guess to get counted");
+        assert_eq!(statistic.total(), 1, "expect one guess to get counted");
+    }
+
+    #[test]
+    fn incorrect_guess() {
+        let mut statistic = Statistic::default();
+
+        statistic.increment_incorrect();
+
+        assert_eq!(statistic.correct(), 0, "expect no correct guess to get counted");
+        assert_eq!(statistic.incorrect(), 1, "expect one incorrect guess to get counted");
+        assert_eq!(statistic.total(), 1, "expect one guess to get counted");
+    }
+
+    #[test]
+    fn near_miss_guess() {
+        let mut statistic = Statistic::default();
+
+        statistic.increment_near_miss();
+
+        assert_eq!(statistic.near_misses(), 1, "expect one near-miss to get counted");
+        assert_eq!(statistic.incorrect(), 1, "expect the near-miss to count as incorrect");
+        assert_eq!(statistic.total(), 1, "expect one guess to get counted");
+    }
+
+    #[test]
+    fn hinted_guess() {
+        let mut statistic = Statistic::default();
+
+        statistic.increment_hints();
+        statistic.increment_hints();
+        statistic.increment_correct();
+        statistic.increment_hinted();
+
+        assert_eq!(statistic.hints(), 2, "expect two hints to get counted");
+        assert_eq!(statistic.hinted(), 1, "expect one hinted guess to get counted");
+        assert_eq!(statistic.correct(), 1, "expect the hinted guess to count as correct");
+    }
+
+    #[test]
+    fn no_word_guess() {
+        let statistic = Statistic::default();
+
+        assert_eq!(statistic.word(0), WordStatistic::default(), "expect an empty statistic for an unseen word");
+    }
+
+    #[test]
+    fn first_try_word_guesses() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 8, 0, 0).unwrap();
+        let mut statistic = Statistic::default();
+
+        statistic.word_mut(1).record(true, true, now);
+        statistic.word_mut(1).record(true, true, now);
+
+        let word = statistic.word(1);
+        assert_eq!(word.attempts(), 2, "expect two attempts to get counted");
+        assert_eq!(word.first_try(), 2, "expect two first try successes to get counted");
+        assert_eq!(word.streak(), 2, "expect a streak of two");
+        assert_eq!(word.last_seen(), Some(now), "expect the word to be seen now");
+        assert_eq!(statistic.word(0), WordStatistic::default(), "expect other words to stay untouched");
+    }
+
+    #[test]
+    fn incorrect_word_guess() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 8, 0, 0).unwrap();
+        let mut statistic = Statistic::default();
+
+        statistic.word_mut(0).record(true, true, now);
+        statistic.word_mut(0).record(false, true, now);
+        statistic.word_mut(0).record(true, false, now);
+
+        let word = statistic.word(0);
+        assert_eq!(word.attempts(), 3, "expect three attempts to get counted");
+        assert_eq!(word.first_try(), 1, "expect one first try success to get counted");
+        assert_eq!(word.streak(), 0, "expect the streak to be broken");
+    }
+}
@@ -0,0 +1,99 @@
+//! The storage in CBOR save files, one for every profile.
+
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use crate::history::Guess;
+use crate::profile::{self, Profile, Profiles};
+use crate::save::{self, Restored};
+use crate::storage::{Error, Storage, Summary};
+use crate::{Trainer, Word};
+
+/// Keeps every profile in its own CBOR save file next to a list of the profiles.
+pub struct FileStorage {
+    profiles: Profiles,
+}
+
+impl FileStorage {
+    /// Opens the profiles in `folder`, see [Profiles::load].
+    pub fn open(folder: &Path) -> Result<Self, Error> {
+        Ok(Self {
+            profiles: Profiles::load(folder)?,
+        })
+    }
+
+    /// Opens the profiles in `folder` even if their list is damaged, see [Profiles::recover].
+    pub fn recover(folder: &Path) -> Result<Self, Error> {
+        Ok(Self {
+            profiles: Profiles::recover(folder)?,
+        })
+    }
+
+    fn profile(&self, id: u32) -> Result<&Profile, Error> {
+        Ok(self.profiles.get(id).ok_or(profile::Error::UnknownProfileError(id))?)
+    }
+
+    /// Reads the trainer of a profile, an empty one if it has no progress yet.
+    fn trainer(&self, id: u32) -> Result<Trainer, Error> {
+        match self.restore(id) {
+            Restored::Missing => Ok(Trainer::default()),
+            Restored::Save(trainer) | Restored::Backup(trainer, _) => Ok(trainer),
+            Restored::Corrupted(error) => Err(error),
+        }
+    }
+}
+
+impl Storage for FileStorage {
+    fn profiles(&self) -> Result<Vec<Profile>, Error> {
+        Ok(self.profiles.profiles().to_vec())
+    }
+
+    fn last_profile(&self) -> Result<Profile, Error> {
+        Ok(self.profiles.last().clone())
+    }
+
+    fn set_last_profile(&mut self, id: u32) -> Result<(), Error> {
+        Ok(self.profiles.set_last(id)?)
+    }
+
+    fn create_profile(&mut self, name: &str) -> Result<Profile, Error> {
+        Ok(self.profiles.create(name)?)
+    }
+
+    fn rename_profile(&mut self, id: u32, name: &str) -> Result<(), Error> {
+        Ok(self.profiles.rename(id, name)?)
+    }
+
+    fn delete_profile(&mut self, id: u32) -> Result<(), Error> {
+        Ok(self.profiles.delete(id)?)
+    }
+
+    fn restore(&self, profile: u32) -> Restored<Error> {
+        match self.profile(profile) {
+            Ok(profile) => save::restore(&self.profiles.save_file(profile)).map_err(Error::from),
+            Err(error) => Restored::Corrupted(error),
+        }
+    }
+
+    fn save(&mut self, profile: u32, trainer: &Trainer) -> Result<(), Error> {
+        let save_file = self.profiles.save_file(self.profile(profile)?);
+        Ok(save::save(trainer, &save_file)?)
+    }
+
+    fn deck(&self, profile: u32) -> Result<Vec<Word>, Error> {
+        Ok(self.trainer(profile)?.words().to_vec())
+    }
+
+    fn history(&self, profile: u32, since: DateTime<Utc>, limit: usize) -> Result<Vec<Guess>, Error> {
+        Ok(self.trainer(profile)?.history().iter()
+            .filter(|guess| guess.time >= since)
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    fn summaries(&self) -> Result<Vec<Summary>, Error> {
+        self.profiles.profiles().iter()
+            .map(|profile| Ok(Summary::new(profile.clone(), &self.trainer(profile.id)?)))
+            .collect()
+    }
+}
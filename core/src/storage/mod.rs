@@ -0,0 +1,524 @@
+//! Where profiles and their progress are kept, and saving it in the background.
+
+pub mod file;
+pub mod sqlite;
+
+use std::fmt::Debug;
+use std::fs::{File, OpenOptions};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::thread::JoinHandle;
+use chrono::{DateTime, Utc};
+use directories::ProjectDirs;
+use fs2::FileExt;
+use lazy_static::lazy_static;
+use thiserror::Error;
+use crate::history::Guess;
+use crate::profile::{self, Profile};
+use crate::save::{self, Restored};
+use crate::settings::{Backend, StorageSettings};
+use crate::storage::file::FileStorage;
+use crate::storage::sqlite::SqliteStorage;
+use crate::{Trainer, Word, STARTER_WORDS};
+
+lazy_static!(
+    /// Where the progress is kept, shared by every frontend.
+    pub static ref SAVE_FOLDER: PathBuf = ProjectDirs::from("at.ac", "tgm", "spelling_trainer").expect("Failed to get project dirs").data_dir().to_owned();
+);
+
+/// The file [SessionLock] locks next to the save files. A database is locked with a `.lock` file next to it.
+const LOCK_FILE: &str = "session.lock";
+
+/// Reasons the storage cannot be read or written.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// The profile list cannot be read or changed.
+    #[error(transparent)]
+    ProfileError(#[from] profile::Error),
+    /// A save file cannot be read or written.
+    #[error(transparent)]
+    SaveError(#[from] save::Error),
+    /// The database cannot be read or written.
+    #[error(transparent)]
+    SqliteError(#[from] rusqlite::Error),
+    /// Another session works with the same progress.
+    #[error("the progress is used by another Spelling Trainer at the moment")]
+    LockedError,
+}
+
+/// Keeps the profiles and everything that belongs to them: the deck they practice, their progress and the history
+/// of their guesses.
+pub trait Storage: Send {
+    /// Returns all profiles in the order they were created.
+    fn profiles(&self) -> Result<Vec<Profile>, Error>;
+
+    /// Returns the profile that was used last, or the first one.
+    fn last_profile(&self) -> Result<Profile, Error>;
+
+    /// Remembers the profile `id` as the one that was used last.
+    fn set_last_profile(&mut self, id: u32) -> Result<(), Error>;
+
+    /// Adds a profile called `name`, which must not be taken yet.
+    fn create_profile(&mut self, name: &str) -> Result<Profile, Error>;
+
+    /// Renames the profile `id` to `name`, which must not be taken by another profile.
+    fn rename_profile(&mut self, id: u32, name: &str) -> Result<(), Error>;
+
+    /// Removes the profile together with its progress. Deleting the last profile creates a new default one.
+    fn delete_profile(&mut self, id: u32) -> Result<(), Error>;
+
+    /// Reads the trainer of a profile.
+    fn restore(&self, profile: u32) -> Restored<Error>;
+
+    /// Writes the trainer of a profile.
+    fn save(&mut self, profile: u32, trainer: &Trainer) -> Result<(), Error>;
+
+    /// Returns the words a profile practices.
+    fn deck(&self, profile: u32) -> Result<Vec<Word>, Error>;
+
+    /// Returns up to `limit` guesses of a profile made since `since`, oldest first.
+    fn history(&self, profile: u32, since: DateTime<Utc>, limit: usize) -> Result<Vec<Guess>, Error>;
+
+    /// Returns an overview of the progress of every profile.
+    fn summaries(&self) -> Result<Vec<Summary>, Error>;
+}
+
+/// Keeps two sessions, e.g. the app and the terminal frontend, from working with the same progress at the same
+/// time. Otherwise the one that saves last would undo everything the other one did.
+///
+/// The lock is advisory and released when the session ends, even if it crashes.
+#[derive(Debug)]
+pub struct SessionLock {
+    _file: File,
+}
+
+impl SessionLock {
+    /// Locks the progress for this session with the lock file at `path`. Fails with [Error::LockedError] if another
+    /// session holds the lock.
+    pub fn acquire(path: &Path) -> Result<Self, Error> {
+        if let Some(folder) = path.parent() {
+            std::fs::create_dir_all(folder).map_err(save::Error::from)?;
+        }
+        let file = OpenOptions::new().create(true).write(true).truncate(false).open(path).map_err(save::Error::from)?;
+        match file.try_lock_exclusive() {
+            Ok(()) => Ok(Self { _file: file }),
+            Err(error) if error.kind() == fs2::lock_contended_error().kind() => Err(Error::LockedError),
+            Err(error) => Err(save::Error::from(error).into()),
+        }
+    }
+}
+
+/// A storage shared between the main thread and the [Autosave] thread.
+pub type SharedStorage = Arc<Mutex<dyn Storage>>;
+
+/// The progress of a profile at a glance.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Summary {
+    /// The profile the progress belongs to.
+    pub profile: Profile,
+    /// The number of words the profile practices.
+    pub words: usize,
+    /// The number of correct guesses.
+    pub correct: usize,
+    /// The number of incorrect guesses.
+    pub incorrect: usize,
+    /// The number of guesses in the history.
+    pub guesses: usize,
+    /// When the last guess in the history was made.
+    pub last_guess: Option<DateTime<Utc>>,
+}
+
+impl Summary {
+    fn new(profile: Profile, trainer: &Trainer) -> Self {
+        Self {
+            profile,
+            words: trainer.words().len(),
+            correct: trainer.statistic().correct(),
+            incorrect: trainer.statistic().incorrect(),
+            guesses: trainer.history().len(),
+            last_guess: trainer.history().iter().last().map(|guess| guess.time),
+        }
+    }
+}
+
+/// Copies every profile with its progress from one storage to another, e.g. when switching backends. Profiles
+/// whose name is taken in `to` already are merged into the existing one.
+pub fn copy(from: &dyn Storage, to: &mut dyn Storage) -> Result<(), Error> {
+    let last = from.last_profile()?;
+    for profile in from.profiles()? {
+        let existing = to.profiles()?.into_iter()
+            .find(|other| other.name.to_lowercase() == profile.name.to_lowercase());
+        let target = match existing {
+            Some(target) => target,
+            None => to.create_profile(&profile.name)?,
+        };
+        match from.restore(profile.id) {
+            Restored::Save(trainer) | Restored::Backup(trainer, _) => to.save(target.id, &trainer)?,
+            Restored::Missing => {}
+            Restored::Corrupted(error) => return Err(error),
+        }
+        if profile.id == last.id {
+            to.set_last_profile(target.id)?;
+        }
+    }
+    Ok(())
+}
+
+/// Opens the storage chosen in the settings and locks it for this session. Problems are added to `warnings` as a
+/// heading and a body. The storage is opened even if another session holds the lock, so the caller decides whether
+/// to go on.
+pub fn open(settings: &StorageSettings, warnings: &mut Vec<(String, String)>) -> (SharedStorage, Result<SessionLock, Error>) {
+    open_in(settings, &SAVE_FOLDER, warnings)
+}
+
+fn open_in(settings: &StorageSettings, folder: &Path, warnings: &mut Vec<(String, String)>) -> (SharedStorage, Result<SessionLock, Error>) {
+    if settings.backend == Backend::Sqlite {
+        let path = settings.database.clone().unwrap_or_else(|| folder.join("progress.sqlite"));
+        // the database may be shared by several computers, so it is locked where it is and not in the save folder
+        let mut lock_file = path.as_os_str().to_owned();
+        lock_file.push(".lock");
+        let session = SessionLock::acquire(Path::new(&lock_file));
+        let result = if path.exists() {
+            SqliteStorage::open(&path)
+        } else {
+            // take the progress along when switching from the save files
+            create_database(&path, folder, warnings)
+        };
+        match result {
+            Ok(database) => return (Arc::new(Mutex::new(database)), session),
+            Err(error) => warnings.push(("Failed to open the database".to_owned(), format!("The progress is kept in save files instead: {error}"))),
+        }
+    }
+    let session = SessionLock::acquire(&folder.join(LOCK_FILE));
+    match open_files(folder, warnings) {
+        Ok(files) => (Arc::new(Mutex::new(files)), session),
+        Err(error) => {
+            warnings.push(("Failed to load profiles".to_owned(), format!("The progress of this session will not be saved: {error}")));
+            (Arc::new(Mutex::new(SqliteStorage::in_memory().expect("An empty database in memory can always be created"))), session)
+        }
+    }
+}
+
+/// Creates the database at `path` with the progress of the save files in `folder`.
+///
+/// The database is built next to `path` and only moved there once the copy is complete. Otherwise a failed copy
+/// would leave an empty database behind, which is opened instead of the save files from then on.
+fn create_database(path: &Path, folder: &Path, warnings: &mut Vec<(String, String)>) -> Result<SqliteStorage, Error> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let temporary = PathBuf::from(temporary);
+    remove_database(&temporary)?;
+    let result = SqliteStorage::open(&temporary).and_then(|mut database| {
+        copy(&open_files(folder, warnings)?, &mut database)
+    });
+    // the database is closed by now, which moves everything from the write-ahead log into the file
+    match result {
+        Ok(()) => std::fs::rename(&temporary, path).map_err(save::Error::from)?,
+        Err(error) => {
+            // the next start tries again anyway
+            let _ = remove_database(&temporary);
+            return Err(error);
+        }
+    }
+    SqliteStorage::open(path)
+}
+
+/// Deletes the database at `path` together with its write-ahead log. Missing files are ignored.
+fn remove_database(path: &Path) -> Result<(), Error> {
+    for suffix in ["", "-wal", "-shm", "-journal"] {
+        let mut file = path.as_os_str().to_owned();
+        file.push(suffix);
+        match std::fs::remove_file(file) {
+            Err(error) if error.kind() != ErrorKind::NotFound => return Err(save::Error::from(error).into()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Opens the save files in `folder`. A damaged profile list is recovered from the save files with a warning.
+fn open_files(folder: &Path, warnings: &mut Vec<(String, String)>) -> Result<FileStorage, Error> {
+    FileStorage::open(folder).or_else(|error| {
+        let files = FileStorage::recover(folder)?;
+        warnings.push(("Failed to load profiles".to_owned(), format!("The profiles were recovered from their progress, but their names are lost: {error}")));
+        Ok(files)
+    })
+}
+
+/// Reads the trainer of a profile together with warnings about anything that went wrong. Profiles without
+/// progress start with the [STARTER_WORDS].
+pub fn restore(storage: &dyn Storage, profile: u32) -> (Trainer, Vec<(String, String)>) {
+    let fresh = || {
+        let mut trainer = Trainer::new(&STARTER_WORDS[..]);
+        trainer.random();
+        trainer
+    };
+    match storage.restore(profile) {
+        Restored::Missing => (fresh(), Vec::new()),
+        Restored::Save(trainer) => (trainer, Vec::new()),
+        Restored::Backup(trainer, error) => (trainer, vec![("Restored progress from a backup".to_owned(), format!("The save file could not be read, so the last backup was restored instead: {error}"))]),
+        Restored::Corrupted(error) => (fresh(), vec![("Failed to restore progress".to_owned(), format!("Neither the save file nor its backup could be read, so the progress was reset: {error}"))]),
+    }
+}
+
+/// Saves the trainer of a profile on a background thread, so the caller never waits for the disk.
+///
+/// Saves that queue up while a previous one is written are collapsed into the newest one.
+pub struct Autosave {
+    sender: Option<Sender<Trainer>>,
+    /// Whether the last save failed, so the next one is written even without changes.
+    failed: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Autosave {
+    /// Starts the thread saving to `profile` in `storage`. Failed saves are passed to `on_error`.
+    pub fn new(storage: SharedStorage, profile: u32, on_error: impl Fn(Error) + Send + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel::<Trainer>();
+        let failed = Arc::new(AtomicBool::new(false));
+        let worker_failed = failed.clone();
+        let worker = std::thread::spawn(move || {
+            while let Ok(mut trainer) = receiver.recv() {
+                while let Ok(newer) = receiver.try_recv() {
+                    trainer = newer;
+                }
+                // a panic in another save does not leave the storage in a state that is worse than a failed save
+                let result = storage.lock().unwrap_or_else(PoisonError::into_inner).save(profile, &trainer);
+                worker_failed.store(result.is_err(), Ordering::SeqCst);
+                if let Err(error) = result {
+                    on_error(error);
+                }
+            }
+        });
+        Self {
+            sender: Some(sender),
+            failed,
+            worker: Some(worker),
+        }
+    }
+
+    /// Queues `trainer` to be saved if it changed since the last save or the last save failed. Returns whether it
+    /// was queued.
+    pub fn save(&self, trainer: &mut Trainer) -> bool {
+        if !trainer.is_dirty() && !self.failed.swap(false, Ordering::SeqCst) {
+            return false;
+        }
+        if let Some(sender) = &self.sender {
+            // the receiver only goes away together with the thread, which lives as long as the sender
+            let _ = sender.send(trainer.clone());
+        }
+        trainer.mark_saved();
+        true
+    }
+
+    /// Waits until every queued save is written.
+    pub fn finish(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        drop(self.sender.take());
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for Autosave {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::storage::file::FileStorage;
+    use crate::storage::sqlite::SqliteStorage;
+    use super::*;
+
+    fn trainer(spelling: &str) -> Trainer {
+        let mut trainer = Trainer::new(vec![Word {
+            word: spelling.to_owned(),
+            alternatives: Vec::new(),
+            url: "https://example.org/".parse().expect("valid url"),
+            credits: String::new(),
+        }]);
+        trainer.reset_statistic();
+        trainer
+    }
+
+    fn storage() -> (tempfile::TempDir, SharedStorage, u32) {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let storage = FileStorage::open(dir.path()).expect("readable storage");
+        let profile = storage.last_profile().expect("default profile").id;
+        (dir, Arc::new(Mutex::new(storage)), profile)
+    }
+
+    fn restore(storage: &SharedStorage, profile: u32) -> Option<Trainer> {
+        match storage.lock().expect("unpoisoned storage").restore(profile) {
+            Restored::Save(trainer) => Some(trainer),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn copy_between_storages() {
+        let (_dir, storage, profile) = storage();
+        let mut from = storage.lock().expect("unpoisoned storage");
+        from.save(profile, &trainer("apple")).expect("saved trainer");
+        let anna = from.create_profile("Anna").expect("new profile");
+        from.save(anna.id, &trainer("dog")).expect("saved trainer");
+        from.set_last_profile(anna.id).expect("existing profile");
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let mut to = SqliteStorage::open(&dir.path().join("progress.sqlite")).expect("database");
+
+        copy(&*from, &mut to).expect("copied profiles");
+
+        let names = to.profiles().expect("profiles").into_iter().map(|profile| profile.name).collect::<Vec<_>>();
+        assert_eq!(names, vec![profile::DEFAULT_NAME, "Anna"], "expect the default profile to be merged and the others to be created");
+        let last = to.last_profile().expect("last profile");
+        assert_eq!(last.name, "Anna", "expect the last profile to be kept");
+        assert!(matches!(to.restore(last.id), Restored::Save(restored) if restored == trainer("dog")), "expect the progress to be copied");
+    }
+
+    #[test]
+    fn create_database_from_files() {
+        let (dir, storage, profile) = storage();
+        storage.lock().expect("unpoisoned storage").save(profile, &trainer("apple")).expect("saved trainer");
+        let path = dir.path().join("progress.sqlite");
+
+        let database = create_database(&path, dir.path(), &mut Vec::new()).expect("database");
+
+        assert!(matches!(database.restore(profile), Restored::Save(restored) if restored == trainer("apple")), "expect the progress to be copied");
+        assert!(!dir.path().join("progress.sqlite.tmp").exists(), "expect no temporary database to be left behind");
+    }
+
+    #[test]
+    fn failed_copy_leaves_no_database() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let folder = dir.path().join("not a folder");
+        std::fs::write(&folder, b"").expect("writable file");
+        let path = dir.path().join("progress.sqlite");
+
+        assert!(create_database(&path, &folder, &mut Vec::new()).is_err(), "expect the copy to fail");
+        assert!(!path.exists(), "expect no database to be created");
+        assert!(!dir.path().join("progress.sqlite.tmp").exists(), "expect the temporary database to be removed");
+    }
+
+    #[test]
+    fn open_unreadable_files() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let folder = dir.path().join("not a folder");
+        std::fs::write(&folder, b"").expect("writable file");
+        let mut warnings = Vec::new();
+
+        assert!(open_files(&folder, &mut warnings).is_err(), "expect the error to be returned instead of a panic");
+        assert!(warnings.is_empty(), "expect no profiles to be recovered");
+    }
+
+    #[test]
+    fn open_recovered_files() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        FileStorage::open(dir.path()).expect("readable storage");
+        std::fs::write(dir.path().join("profiles.toml"), "not = [a profile list").expect("writable profile list");
+        let mut warnings = Vec::new();
+
+        let files = open_files(dir.path(), &mut warnings).expect("recovered profiles");
+
+        assert_eq!(files.profiles().expect("profiles").len(), 1, "expect the default profile to be recovered");
+        assert_eq!(warnings.len(), 1, "expect the recovery to be reported");
+    }
+
+    #[test]
+    fn lock_session() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+
+        let path = dir.path().join(LOCK_FILE);
+        let lock = SessionLock::acquire(&path).expect("free lock");
+
+        assert!(matches!(SessionLock::acquire(&path), Err(Error::LockedError)), "expect a second session to be refused");
+        drop(lock);
+        assert!(SessionLock::acquire(&path).is_ok(), "expect the lock to be released with the session");
+    }
+
+    #[test]
+    fn lock_storage_in_use() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let database = tempfile::tempdir().expect("temporary directory");
+        let files = StorageSettings::default();
+        let sqlite = StorageSettings {
+            backend: Backend::Sqlite,
+            database: Some(database.path().join("progress.sqlite")),
+        };
+        let mut warnings = Vec::new();
+
+        let (_storage, session) = open_in(&sqlite, dir.path(), &mut warnings);
+        let _session = session.expect("free database");
+
+        assert!(matches!(open_in(&sqlite, dir.path(), &mut warnings).1, Err(Error::LockedError)), "expect the database to be locked");
+        assert!(database.path().join("progress.sqlite.lock").exists(), "expect the lock next to the database");
+        assert!(open_in(&files, dir.path(), &mut warnings).1.is_ok(), "expect the save files not to be locked by the database");
+        assert!(warnings.is_empty(), "expect no warnings, got {warnings:?}");
+    }
+
+    #[test]
+    fn autosave_changed_trainer() {
+        let (_dir, storage, profile) = storage();
+        let autosave = Autosave::new(storage.clone(), profile, |error| panic!("expect the save to be written: {error}"));
+        let mut trainer = trainer("apple");
+        trainer.mark_saved();
+
+        assert!(!autosave.save(&mut trainer), "expect an unchanged trainer not to be saved");
+        trainer.reset_statistic();
+        assert!(autosave.save(&mut trainer), "expect a changed trainer to be saved");
+        assert!(!trainer.is_dirty(), "expect the trainer to be marked as saved");
+        autosave.finish();
+
+        assert_eq!(restore(&storage, profile), Some(trainer), "expect the trainer to be written");
+    }
+
+    #[test]
+    fn autosave_writes_newest_save() {
+        let (_dir, storage, profile) = storage();
+        let autosave = Autosave::new(storage.clone(), profile, |error| panic!("expect the save to be written: {error}"));
+
+        for spelling in ["apple", "dog", "cat"] {
+            autosave.save(&mut trainer(spelling));
+        }
+        autosave.finish();
+
+        assert_eq!(restore(&storage, profile), Some(trainer("cat")), "expect the last change to be written");
+    }
+
+    #[test]
+    fn autosave_reports_errors() {
+        let (_dir, storage, _) = storage();
+        let (sender, receiver) = mpsc::channel();
+        let autosave = Autosave::new(storage, 99, move |error| sender.send(error).expect("open channel"));
+
+        autosave.save(&mut trainer("apple"));
+        autosave.finish();
+
+        assert!(matches!(receiver.try_recv(), Ok(Error::ProfileError(profile::Error::UnknownProfileError(99)))), "expect the failed save to be reported");
+    }
+
+    #[test]
+    fn autosave_retries_failed_save() {
+        let (_dir, storage, profile) = storage();
+        let (sender, receiver) = mpsc::channel();
+        let autosave = Autosave::new(storage.clone(), profile + 1, move |error| sender.send(error).expect("open channel"));
+        let mut trainer = trainer("apple");
+
+        autosave.save(&mut trainer);
+        receiver.recv().expect("failed save");
+        let anna = storage.lock().expect("unpoisoned storage").create_profile("Anna").expect("new profile");
+        assert_eq!(anna.id, profile + 1, "expect the profile of the autosave to exist now");
+
+        assert!(autosave.save(&mut trainer), "expect the unchanged trainer to be saved again");
+        autosave.finish();
+        assert_eq!(restore(&storage, anna.id), Some(trainer), "expect the retry to be written");
+    }
+}
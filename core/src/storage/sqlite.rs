@@ -0,0 +1,597 @@
+//! The storage in a single SQLite database.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row, Transaction};
+use rusqlite::types::Type;
+use serde::{Deserialize, Serialize};
+use crate::hint::Hints;
+use crate::history::{Guess, History, Retention};
+use crate::matching::MatchPolicy;
+use crate::profile::{self, Profile, DEFAULT_NAME};
+use crate::save::{self, Restored};
+use crate::schedule::Schedule;
+use crate::source::ImageSource;
+use crate::statistic::Statistic;
+use crate::storage::{Error, Storage, Summary};
+use crate::{Trainer, Word};
+
+/// The version of the database schema, kept in `PRAGMA user_version`.
+const SCHEMA_VERSION: u32 = 2;
+
+const SCHEMA: &str = "
+    CREATE TABLE profiles (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL
+    );
+    CREATE TABLE meta (
+        key TEXT PRIMARY KEY,
+        value INTEGER NOT NULL
+    );
+    CREATE TABLE progress (
+        profile INTEGER PRIMARY KEY REFERENCES profiles (id) ON DELETE CASCADE,
+        correct INTEGER NOT NULL,
+        incorrect INTEGER NOT NULL,
+        state BLOB NOT NULL
+    );
+    CREATE TABLE words (
+        profile INTEGER NOT NULL REFERENCES profiles (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        word TEXT NOT NULL,
+        alternatives TEXT NOT NULL,
+        url TEXT NOT NULL,
+        credits TEXT NOT NULL,
+        PRIMARY KEY (profile, position)
+    );
+    CREATE TABLE guesses (
+        id INTEGER PRIMARY KEY,
+        profile INTEGER NOT NULL REFERENCES profiles (id) ON DELETE CASCADE,
+        word TEXT NOT NULL,
+        guess TEXT NOT NULL,
+        correct INTEGER NOT NULL,
+        time TEXT NOT NULL,
+        duration_ns INTEGER,
+        hints INTEGER NOT NULL
+    );
+    CREATE INDEX guesses_by_time ON guesses (profile, time);
+";
+
+/// The part of a trainer that is not spread over the words and guesses tables.
+#[derive(Serialize, Deserialize)]
+struct Progress {
+    selected: Option<usize>,
+    statistic: Statistic,
+    schedule: Schedule,
+    missed: bool,
+    hints: Hints,
+    asked: Option<DateTime<Utc>>,
+    retention: Retention,
+    policy: MatchPolicy,
+    directory: Option<PathBuf>,
+    #[serde(default)]
+    image_failures: BTreeMap<String, u32>,
+}
+
+/// Keeps all profiles in one SQLite database, so their progress can be queried without loading every trainer.
+///
+/// Guess times are stored as RFC 3339 text in UTC with microseconds, which sorts chronologically and works with the
+/// date functions of SQLite.
+pub struct SqliteStorage {
+    connection: Connection,
+}
+
+impl SqliteStorage {
+    /// Opens the database at `path`, creating it if necessary. Without any profiles, a default one is created.
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        if let Some(folder) = path.parent() {
+            std::fs::create_dir_all(folder).map_err(save::Error::from)?;
+        }
+        Self::setup(Connection::open(path)?)
+    }
+
+    /// Opens a database that only lives as long as the storage, with a default profile.
+    pub fn in_memory() -> Result<Self, Error> {
+        Self::setup(Connection::open_in_memory()?)
+    }
+
+    fn setup(connection: Connection) -> Result<Self, Error> {
+        connection.pragma_update(None, "foreign_keys", true)?;
+        // lets teachers read the database while the trainer writes to it
+        connection.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+        connection.busy_timeout(Duration::from_secs(5))?;
+        let version: u32 = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version == 0 {
+            connection.execute_batch(SCHEMA)?;
+            connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        } else if version > SCHEMA_VERSION {
+            return Err(save::Error::UnsupportedVersionError(version).into());
+        }
+
+        let mut storage = Self {
+            connection,
+        };
+        if version == 1 {
+            storage.migrate_v1()?;
+        }
+        if storage.profiles()?.is_empty() {
+            storage.create_profile(DEFAULT_NAME)?;
+        }
+        Ok(storage)
+    }
+
+    /// Version 1 counted the failed image loads in the words table. Version 2 keeps them with the progress.
+    fn migrate_v1(&mut self) -> Result<(), Error> {
+        let transaction = self.connection.transaction()?;
+        let states = transaction.prepare("SELECT profile, state FROM progress")?
+            .query_map([], |row| Ok((row.get::<_, u32>(0)?, row.get::<_, Vec<u8>>(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        for (profile, state) in states {
+            let mut progress: Progress = ciborium::from_reader(&state[..]).map_err(save::Error::from)?;
+            progress.image_failures = transaction.prepare("SELECT word, image_failures FROM words WHERE profile = ?1 AND image_failures > 0")?
+                .query_map([profile], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<_, _>>()?;
+            let mut state = Vec::new();
+            ciborium::into_writer(&progress, &mut state).map_err(save::Error::from)?;
+            transaction.execute("UPDATE progress SET state = ?1 WHERE profile = ?2", params![state, profile])?;
+        }
+        transaction.execute_batch("ALTER TABLE words DROP COLUMN image_failures")?;
+        transaction.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn check_profile(&self, id: u32) -> Result<(), Error> {
+        let exists = self.connection.query_row("SELECT 1 FROM profiles WHERE id = ?1", [id], |_| Ok(())).optional()?;
+        exists.ok_or(profile::Error::UnknownProfileError(id).into())
+    }
+
+    fn trainer(&self, profile: u32) -> Result<Option<Trainer>, Error> {
+        self.check_profile(profile)?;
+        let state = self.connection.query_row("SELECT state FROM progress WHERE profile = ?1", [profile], |row| row.get::<_, Vec<u8>>(0))
+            .optional()?;
+        let Some(state) = state else {
+            return Ok(None);
+        };
+        let progress: Progress = ciborium::from_reader(&state[..]).map_err(save::Error::from)?;
+
+        let words = self.connection.prepare("SELECT word, alternatives, url, credits FROM words WHERE profile = ?1 ORDER BY position")?
+            .query_map([profile], word)?
+            .collect::<Result<Vec<_>, _>>()?;
+        let guesses = self.connection.prepare("SELECT word, guess, correct, time, duration_ns, hints FROM guesses WHERE profile = ?1 ORDER BY id")?
+            .query_map([profile], guess)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Some(Trainer {
+            words,
+            selected: progress.selected,
+            statistic: progress.statistic,
+            schedule: progress.schedule,
+            missed: progress.missed,
+            hints: progress.hints,
+            asked: progress.asked,
+            history: History::from_guesses(guesses, progress.retention),
+            policy: progress.policy,
+            directory: progress.directory,
+            image_failures: progress.image_failures,
+            ..Default::default()
+        }))
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn profiles(&self) -> Result<Vec<Profile>, Error> {
+        Ok(self.connection.prepare("SELECT id, name FROM profiles ORDER BY id")?
+            .query_map([], |row| Ok(Profile { id: row.get(0)?, name: row.get(1)? }))?
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    fn last_profile(&self) -> Result<Profile, Error> {
+        let profile = self.connection.query_row(
+            "SELECT id, name FROM profiles ORDER BY id = (SELECT value FROM meta WHERE key = 'last_profile') DESC, id LIMIT 1",
+            [],
+            |row| Ok(Profile { id: row.get(0)?, name: row.get(1)? }),
+        )?;
+        Ok(profile)
+    }
+
+    fn set_last_profile(&mut self, id: u32) -> Result<(), Error> {
+        self.check_profile(id)?;
+        self.connection.execute("INSERT INTO meta (key, value) VALUES ('last_profile', ?1) ON CONFLICT (key) DO UPDATE SET value = excluded.value", [id])?;
+        Ok(())
+    }
+
+    fn create_profile(&mut self, name: &str) -> Result<Profile, Error> {
+        let name = profile::validate_name(name, &self.profiles()?, None)?;
+        self.connection.execute("INSERT INTO profiles (name) VALUES (?1)", [&name])?;
+        Ok(Profile {
+            id: self.connection.last_insert_rowid() as u32,
+            name,
+        })
+    }
+
+    fn rename_profile(&mut self, id: u32, name: &str) -> Result<(), Error> {
+        self.check_profile(id)?;
+        let name = profile::validate_name(name, &self.profiles()?, Some(id))?;
+        self.connection.execute("UPDATE profiles SET name = ?1 WHERE id = ?2", params![name, id])?;
+        Ok(())
+    }
+
+    fn delete_profile(&mut self, id: u32) -> Result<(), Error> {
+        self.check_profile(id)?;
+        self.connection.execute("DELETE FROM profiles WHERE id = ?1", [id])?;
+        if self.profiles()?.is_empty() {
+            self.create_profile(DEFAULT_NAME)?;
+        }
+        Ok(())
+    }
+
+    fn restore(&self, profile: u32) -> Restored<Error> {
+        match self.trainer(profile) {
+            Ok(Some(trainer)) => Restored::Save(trainer),
+            Ok(None) => Restored::Missing,
+            Err(error) => Restored::Corrupted(error),
+        }
+    }
+
+    fn save(&mut self, profile: u32, trainer: &Trainer) -> Result<(), Error> {
+        self.check_profile(profile)?;
+        let progress = Progress {
+            selected: trainer.selected,
+            statistic: trainer.statistic.clone(),
+            schedule: trainer.schedule.clone(),
+            missed: trainer.missed,
+            hints: trainer.hints.clone(),
+            asked: trainer.asked,
+            retention: trainer.history.retention().clone(),
+            policy: trainer.policy.clone(),
+            directory: trainer.directory.clone(),
+            image_failures: trainer.image_failures.clone(),
+        };
+        let mut state = Vec::new();
+        ciborium::into_writer(&progress, &mut state).map_err(save::Error::from)?;
+
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "INSERT INTO progress (profile, correct, incorrect, state) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (profile) DO UPDATE SET correct = excluded.correct, incorrect = excluded.incorrect, state = excluded.state",
+            params![profile, trainer.statistic.correct(), trainer.statistic.incorrect(), state],
+        )?;
+        transaction.execute("DELETE FROM words WHERE profile = ?1", [profile])?;
+        {
+            let mut insert = transaction.prepare("INSERT INTO words (profile, position, word, alternatives, url, credits) VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?;
+            for (position, word) in trainer.words.iter().enumerate() {
+                let alternatives = serde_json::to_string(&word.alternatives).expect("A list of strings is always valid json");
+                insert.execute(params![profile, position, word.word, alternatives, word.url.to_string(), word.credits])?;
+            }
+        }
+        save_history(&transaction, profile, &trainer.history)?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn deck(&self, profile: u32) -> Result<Vec<Word>, Error> {
+        self.check_profile(profile)?;
+        Ok(self.connection.prepare("SELECT word, alternatives, url, credits FROM words WHERE profile = ?1 ORDER BY position")?
+            .query_map([profile], word)?
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    fn history(&self, profile: u32, since: DateTime<Utc>, limit: usize) -> Result<Vec<Guess>, Error> {
+        self.check_profile(profile)?;
+        Ok(self.connection.prepare("SELECT word, guess, correct, time, duration_ns, hints FROM guesses WHERE profile = ?1 AND time >= ?2 ORDER BY id LIMIT ?3")?
+            .query_map(params![profile, format_time(since), limit.min(i64::MAX as usize) as i64], guess)?
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    fn summaries(&self) -> Result<Vec<Summary>, Error> {
+        Ok(self.connection.prepare("
+            SELECT profiles.id, profiles.name,
+                (SELECT count(*) FROM words WHERE words.profile = profiles.id),
+                coalesce(progress.correct, 0), coalesce(progress.incorrect, 0),
+                (SELECT count(*) FROM guesses WHERE guesses.profile = profiles.id),
+                (SELECT max(time) FROM guesses WHERE guesses.profile = profiles.id)
+            FROM profiles LEFT JOIN progress ON progress.profile = profiles.id
+            ORDER BY profiles.id
+        ")?
+            .query_map([], |row| Ok(Summary {
+                profile: Profile { id: row.get(0)?, name: row.get(1)? },
+                words: row.get(2)?,
+                correct: row.get(3)?,
+                incorrect: row.get(4)?,
+                guesses: row.get(5)?,
+                last_guess: row.get::<_, Option<String>>(6)?.map(|time| parse_time(6, &time)).transpose()?,
+            }))?
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+}
+
+/// Brings the stored guesses of a profile in line with `history`.
+///
+/// A history only grows at the end and is pruned at the start, so usually only the new guesses are inserted. If the
+/// stored guesses do not match up, they are all written again.
+fn save_history(transaction: &Transaction, profile: u32, history: &History) -> Result<(), Error> {
+    let guesses = history.iter().collect::<Vec<_>>();
+    match guesses.first() {
+        Some(oldest) => transaction.execute("DELETE FROM guesses WHERE profile = ?1 AND time < ?2", params![profile, format_time(oldest.time)])?,
+        None => transaction.execute("DELETE FROM guesses WHERE profile = ?1", [profile])?,
+    };
+
+    // guesses at the same time as the oldest one may have been pruned from the history but are still stored, so the
+    // first stored guess has to match as well as the last one
+    let stored: usize = transaction.query_row("SELECT count(*) FROM guesses WHERE profile = ?1", [profile], |row| row.get(0))?;
+    let first = transaction.query_row("SELECT word, guess, correct, time, duration_ns, hints FROM guesses WHERE profile = ?1 ORDER BY id LIMIT 1", [profile], guess)
+        .optional()?;
+    let last = transaction.query_row("SELECT word, guess, correct, time, duration_ns, hints FROM guesses WHERE profile = ?1 ORDER BY id DESC LIMIT 1", [profile], guess)
+        .optional()?;
+    let matches = match (guesses.first(), stored.checked_sub(1).and_then(|idx| guesses.get(idx)), &first, &last) {
+        (Some(&oldest), Some(&newest), Some(first), Some(last)) => oldest == first && newest == last,
+        (_, None, None, None) => true,
+        _ => false,
+    };
+    let new = if matches {
+        &guesses[stored..]
+    } else {
+        transaction.execute("DELETE FROM guesses WHERE profile = ?1", [profile])?;
+        &guesses[..]
+    };
+
+    let mut insert = transaction.prepare("INSERT INTO guesses (profile, word, guess, correct, time, duration_ns, hints) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)")?;
+    for guess in new {
+        let duration = guess.duration.map(|duration| duration.as_nanos().min(i64::MAX as u128) as i64);
+        insert.execute(params![profile, guess.word, guess.guess, guess.correct, format_time(guess.time), duration, guess.hints])?;
+    }
+    Ok(())
+}
+
+fn word(row: &Row) -> rusqlite::Result<Word> {
+    let alternatives = row.get::<_, String>(1)?;
+    let url = row.get::<_, String>(2)?;
+    Ok(Word {
+        word: row.get(0)?,
+        alternatives: serde_json::from_str(&alternatives)
+            .map_err(|error| rusqlite::Error::FromSqlConversionFailure(1, Type::Text, Box::new(error)))?,
+        url: ImageSource::from_str(&url)
+            .map_err(|error| rusqlite::Error::FromSqlConversionFailure(2, Type::Text, Box::new(error)))?,
+        credits: row.get(3)?,
+    })
+}
+
+fn guess(row: &Row) -> rusqlite::Result<Guess> {
+    Ok(Guess {
+        word: row.get(0)?,
+        guess: row.get(1)?,
+        correct: row.get(2)?,
+        time: parse_time(3, &row.get::<_, String>(3)?)?,
+        duration: row.get::<_, Option<i64>>(4)?.map(|nanos| Duration::from_nanos(nanos.max(0) as u64)),
+        hints: row.get(5)?,
+    })
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+fn parse_time(column: usize, time: &str) -> rusqlite::Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(time)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|error| rusqlite::Error::FromSqlConversionFailure(column, Type::Text, Box::new(error)))
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+    use crate::storage::file::FileStorage;
+    use super::*;
+
+    fn time(minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 1, 8, minute, 0).unwrap()
+    }
+
+    fn trainer() -> Trainer {
+        let mut trainer = Trainer::new(vec![
+            Word {
+                word: "apple".to_owned(),
+                alternatives: Vec::new(),
+                url: ImageSource::from_str("https://example.org/apple.png").expect("valid url"),
+                credits: "apple by someone".to_owned(),
+            },
+            Word {
+                word: "dog".to_owned(),
+                alternatives: vec!["puppy".to_owned()],
+                url: ImageSource::from_str("images/dog.png").expect("valid path"),
+                credits: String::new(),
+            },
+        ]);
+        trainer.record_image_failure("dog");
+        trainer.record_image_failure("dog");
+        trainer.set_directory(Some(PathBuf::from("/decks/animals")));
+        trainer.select_at(0, time(0));
+        trainer.guess_at("apple", time(1));
+        trainer.select_at(1, time(2));
+        trainer.guess_at("cat", time(3));
+        trainer
+    }
+
+    fn in_memory() -> SqliteStorage {
+        SqliteStorage::in_memory().expect("database")
+    }
+
+    fn default_profile(storage: &SqliteStorage) -> u32 {
+        storage.last_profile().expect("default profile").id
+    }
+
+    #[test]
+    fn default_profile_in_new_database() {
+        let storage = in_memory();
+
+        assert_eq!(storage.profiles().expect("profiles"), vec![Profile { id: 1, name: DEFAULT_NAME.to_owned() }], "expect a default profile");
+        assert!(matches!(storage.restore(1), Restored::Missing), "expect no progress yet");
+    }
+
+    #[test]
+    fn save_and_restore() {
+        let mut storage = in_memory();
+        let profile = default_profile(&storage);
+
+        storage.save(profile, &trainer()).expect("saved trainer");
+
+        assert!(matches!(storage.restore(profile), Restored::Save(restored) if restored == trainer()), "expect the whole trainer to be restored");
+        assert_eq!(storage.deck(profile).expect("deck"), trainer().words(), "expect the deck to be stored");
+    }
+
+    #[test]
+    fn append_new_guesses() {
+        let mut storage = in_memory();
+        let profile = default_profile(&storage);
+        let mut trainer = trainer();
+        storage.save(profile, &trainer).expect("saved trainer");
+        let first_id: i64 = storage.connection.query_row("SELECT min(id) FROM guesses", [], |row| row.get(0)).expect("stored guesses");
+
+        trainer.guess_at("dog", time(4));
+        storage.save(profile, &trainer).expect("saved trainer");
+
+        let ids: i64 = storage.connection.query_row("SELECT min(id) FROM guesses", [], |row| row.get(0)).expect("stored guesses");
+        assert_eq!(ids, first_id, "expect the stored guesses to be kept");
+        assert_eq!(storage.history(profile, time(0), 10).expect("history").len(), 3, "expect the new guess to be added");
+        assert!(matches!(storage.restore(profile), Restored::Save(restored) if restored == trainer), "expect the whole trainer to be restored");
+    }
+
+    #[test]
+    fn prune_and_rewrite_guesses() {
+        let mut storage = in_memory();
+        let profile = default_profile(&storage);
+        let mut trainer = trainer();
+        storage.save(profile, &trainer).expect("saved trainer");
+
+        trainer.history = History::from_guesses(trainer.history.iter().skip(1).cloned(), Retention::default());
+        storage.save(profile, &trainer).expect("saved trainer");
+        assert_eq!(storage.history(profile, time(0), 10).expect("history"), trainer.history.iter().cloned().collect::<Vec<_>>(), "expect pruned guesses to be removed");
+
+        trainer.history = History::default();
+        storage.save(profile, &trainer).expect("saved trainer");
+        assert!(storage.history(profile, time(0), 10).expect("history").is_empty(), "expect a cleared history to be removed");
+    }
+
+    #[test]
+    fn prune_guesses_at_the_same_time() {
+        let mut storage = in_memory();
+        let profile = default_profile(&storage);
+        let mut trainer = trainer();
+        let guess = |text: &str| Guess {
+            word: "apple".to_owned(),
+            guess: text.to_owned(),
+            correct: false,
+            time: time(5),
+            duration: None,
+            hints: 0,
+        };
+        trainer.history = History::from_guesses([guess("appel"), guess("aple")], Retention::default());
+        storage.save(profile, &trainer).expect("saved trainer");
+
+        trainer.history = History::from_guesses([guess("aple"), guess("aple")], Retention::default());
+        storage.save(profile, &trainer).expect("saved trainer");
+
+        assert_eq!(storage.history(profile, time(0), 10).expect("history"), trainer.history.iter().cloned().collect::<Vec<_>>(), "expect the pruned guess to be removed");
+    }
+
+    #[test]
+    fn query_history() {
+        let mut storage = in_memory();
+        let profile = default_profile(&storage);
+        storage.save(profile, &trainer()).expect("saved trainer");
+
+        let history = storage.history(profile, time(2), 10).expect("history");
+
+        assert_eq!(history.iter().map(|guess| guess.guess.as_str()).collect::<Vec<_>>(), vec!["cat"], "expect only guesses since the time");
+        assert_eq!(history[0].duration, Some(Duration::from_secs(60)), "expect the duration to be stored");
+        assert_eq!(storage.history(profile, time(0), 1).expect("history").len(), 1, "expect the limit to be applied");
+    }
+
+    #[test]
+    fn summaries_across_profiles() {
+        let mut storage = in_memory();
+        let profile = default_profile(&storage);
+        let anna = storage.create_profile("Anna").expect("new profile");
+        storage.save(profile, &trainer()).expect("saved trainer");
+
+        let summaries = storage.summaries().expect("summaries");
+
+        assert_eq!(summaries, vec![
+            Summary { profile: Profile { id: profile, name: DEFAULT_NAME.to_owned() }, words: 2, correct: 1, incorrect: 1, guesses: 2, last_guess: Some(time(3)) },
+            Summary { profile: anna, words: 0, correct: 0, incorrect: 0, guesses: 0, last_guess: None },
+        ], "expect the progress of every profile");
+    }
+
+    #[test]
+    fn manage_profiles() {
+        let mut storage = in_memory();
+        let anna = storage.create_profile("Anna").expect("new profile");
+        storage.save(anna.id, &trainer()).expect("saved trainer");
+
+        assert!(matches!(storage.create_profile("anna"), Err(Error::ProfileError(profile::Error::DuplicateNameError(_)))), "expect a taken name to be rejected");
+        storage.rename_profile(anna.id, "Anne").expect("renamed profile");
+        storage.set_last_profile(anna.id).expect("existing profile");
+        assert_eq!(storage.last_profile().expect("last profile").name, "Anne", "expect the last profile to be remembered");
+
+        storage.delete_profile(anna.id).expect("deleted profile");
+        assert_eq!(storage.last_profile().expect("last profile").name, DEFAULT_NAME, "expect another profile to be used");
+        let guesses: usize = storage.connection.query_row("SELECT count(*) FROM guesses", [], |row| row.get(0)).expect("count");
+        assert_eq!(guesses, 0, "expect the progress of the profile to be removed");
+        assert!(matches!(storage.save(anna.id, &trainer()), Err(Error::ProfileError(profile::Error::UnknownProfileError(_)))), "expect a deleted profile to be rejected");
+    }
+
+    #[test]
+    fn reopen_database() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let path = dir.path().join("progress.sqlite");
+        let mut storage = SqliteStorage::open(&path).expect("database");
+        let profile = default_profile(&storage);
+        storage.save(profile, &trainer()).expect("saved trainer");
+        drop(storage);
+
+        let storage = SqliteStorage::open(&path).expect("database");
+
+        assert_eq!(storage.profiles().expect("profiles").len(), 1, "expect no further default profile");
+        assert!(matches!(storage.restore(profile), Restored::Save(restored) if restored == trainer()), "expect the trainer to be kept");
+    }
+
+    #[test]
+    fn migrate_v1_database() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let path = dir.path().join("progress.sqlite");
+        let mut storage = SqliteStorage::open(&path).expect("database");
+        let profile = default_profile(&storage);
+        let mut trainer = trainer();
+        trainer.record_image_loaded("dog");
+        storage.save(profile, &trainer).expect("saved trainer");
+        storage.connection.execute_batch("
+            ALTER TABLE words ADD COLUMN image_failures INTEGER NOT NULL DEFAULT 0;
+            UPDATE words SET image_failures = 2 WHERE word = 'dog';
+            PRAGMA user_version = 1;
+        ").expect("version 1 database");
+        drop(storage);
+
+        let storage = SqliteStorage::open(&path).expect("database");
+
+        assert!(matches!(storage.restore(profile), Restored::Save(restored) if restored == self::trainer()), "expect the image failures to be moved to the progress");
+        let version: u32 = storage.connection.pragma_query_value(None, "user_version", |row| row.get(0)).expect("version");
+        assert_eq!(version, SCHEMA_VERSION, "expect the schema to be upgraded");
+    }
+
+    #[test]
+    fn same_behaviour_as_file_storage() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let mut file = FileStorage::open(dir.path()).expect("file storage");
+        let mut sqlite = in_memory();
+
+        for storage in [&mut file as &mut dyn Storage, &mut sqlite] {
+            let profile = storage.last_profile().expect("default profile").id;
+            storage.save(profile, &trainer()).expect("saved trainer");
+            assert_eq!(storage.history(profile, time(2), 10).expect("history").len(), 1, "expect the same history");
+            assert_eq!(storage.summaries().expect("summaries")[0].guesses, 2, "expect the same summary");
+        }
+    }
+}
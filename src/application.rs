@@ -1,43 +1,30 @@
 use std::cell::RefCell;
-use std::path::PathBuf;
+use std::error::Error;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::sync::mpsc::Sender;
+use std::sync::{MutexGuard, PoisonError};
+use std::time::Duration;
 use adw::{gio, glib};
-use adw::glib::{closure_local, Object};
+use adw::glib::{clone, closure_local, MainContext, Object};
 use adw::prelude::*;
 use adw::subclass::prelude::*;
-use directories::ProjectDirs;
 use lazy_static::lazy_static;
-use url::Url;
-use crate::model::{Trainer, Word};
+use spelling_trainer_core::{export, image, import, Trainer, Word};
+use spelling_trainer_core::bundle::{is_valid_extension, Bundle};
+use spelling_trainer_core::matching::MatchPolicy;
+use spelling_trainer_core::profile::Profile;
+use spelling_trainer_core::storage::{self, Autosave, SharedStorage, Storage, SAVE_FOLDER};
+use crate::view::profiles::ProfileWindow;
 use crate::view::statistic::StatisticWindow;
+use crate::view::web_image::util;
 use crate::view::window::Window;
 
 const APP_ID: &str = "at.ac.tgm.pdamianik.spelling_trainer";
+/// How long the trainer has to stay unchanged before it is saved.
+const AUTOSAVE_DELAY: Duration = Duration::from_secs(2);
 lazy_static!{
-    static ref SAVE_FOLDER: PathBuf = ProjectDirs::from("at.ac", "tgm", "spelling_trainer").expect("Failed to get project dirs").data_dir().to_owned();
-    static ref SAVE_FILE: PathBuf = SAVE_FOLDER.join("save.cbor");
-    static ref WORDS: [Word; 4] = [
-        Word {
-            word: "apple".to_owned(),
-            url: Url::parse("https://external-content.duckduckgo.com/iu/?u=http%3A%2F%2Fwikiclipart.com%2Fwp-content%2Fuploads%2F2016%2F09%2Fclip-art-apple-free-clipart.png&f=1&nofb=1&ipt=8d2d625dc550c18588574defee43dedc1906b3ff464fb1afab521c3426ed6f0e&ipo=images").expect("Failed to parse builtin image url"),
-            credits: "apple https://wikiclipart.com/apple-clipart_480/".to_owned(),
-        },
-        Word {
-            word: "raspberry".to_owned(),
-            url: Url::parse("https://external-content.duckduckgo.com/iu/?u=http%3A%2F%2Fclipartmag.com%2Fimages%2Fraspberry-clipart-27.png&f=1&nofb=1&ipt=e7b96c092eb38787f696e47f6c80cb23b5e7c299a538f01a099597fb06ba0f21&ipo=images").expect("Failed to parse builtin image url"),
-            credits: "raspberry https://clipartmag.com/download-clipart-image#raspberry-clipart-27.png".to_owned(),
-        },
-        Word {
-            word: "dog".to_owned(),
-            url: Url::parse("https://external-content.duckduckgo.com/iu/?u=https%3A%2F%2Fwallpaperboat.com%2Fwp-content%2Fuploads%2F2021%2F05%2F13%2F77274%2Fdoge-meme-11.jpg&f=1&nofb=1&ipt=2c90776ba562173dcbda96b9fa10110e2ec577700a7f8ec511dc9a2825b644b9&ipo=images").expect("Failed to parse builtin image url"),
-            credits: "dog https://wallpaperboat.com/doge-meme-wallpapers".to_owned(),
-        },
-        Word {
-            word: "cat".to_owned(),
-            url: Url::parse("https://external-content.duckduckgo.com/iu/?u=https%3A%2F%2Fthecaninebuddy.com%2Fwp-content%2Fuploads%2F2021%2F08%2Fcrying-cat-meme.jpg&f=1&nofb=1&ipt=e2f2214f7587939060fef5208b166c8e19269d2a4b92b6185f8f83119bff266b&ipo=images").expect("Failed to parse builtin image url"),
-            credits: "cat https://thecaninebuddy.com/crying-cat-meme-know-when-you-should-use-it/".to_owned(),
-        }
-    ];
+    static ref DECK_FOLDER: PathBuf = SAVE_FOLDER.join("decks");
 }
 
 glib::wrapper! {
@@ -61,13 +48,30 @@ impl Application {
     }
 
     pub fn present_window(&self) {
-        let window = if let Some(window) = self.active_window() {
-            window
+        let (window, created) = if let Some(window) = self.active_window() {
+            (window, false)
         } else {
             let window = Window::new(self);
-            window.upcast()
+            (window.upcast(), true)
         };
+        self.update_title();
         window.present();
+
+        let warnings = self.imp().warnings.take();
+        for (heading, body) in warnings {
+            self.show_message(&heading, &body);
+        }
+        // shared computers start with the question who is learning
+        if created && self.profiles().len() > 1 {
+            self.show_profiles_dialog();
+        }
+    }
+
+    /// Applies the network settings. Problems are shown once the window is presented.
+    fn configure_network(&self) {
+        if let Err(error) = image::configure(self.imp().settings.network.clone()) {
+            self.imp().warnings.borrow_mut().push(("Failed to load settings".to_owned(), error.to_string()));
+        }
     }
 
     pub fn show_about_dialog(&self) {
@@ -80,7 +84,11 @@ impl Application {
             .developers(vec!["Philip Damianik"])
             .build();
 
-        dialog.add_credit_section(Some("Images"), &WORDS.iter().map(|word| word.credits.as_str()).collect::<Vec<_>>());
+        let credits = self.imp().trainer.borrow().words().iter()
+            .map(|word| word.credits.clone())
+            .filter(|credits| !credits.is_empty())
+            .collect::<Vec<_>>();
+        dialog.add_credit_section(Some("Images"), &credits.iter().map(String::as_str).collect::<Vec<_>>());
 
         dialog.present();
     }
@@ -92,6 +100,339 @@ impl Application {
         dialog.present();
     }
 
+    pub fn show_profiles_dialog(&self) {
+        let window = self.active_window().unwrap();
+        let dialog = ProfileWindow::new(self, &window);
+
+        dialog.present();
+    }
+
+    pub fn show_import_dialog(&self) {
+        let window = self.active_window().unwrap();
+        let dialog = gtk::FileDialog::builder()
+            .title("Import word list")
+            .modal(true)
+            .filters(&word_list_filters())
+            .build();
+
+        MainContext::default().spawn_local(clone!(@weak self as application => async move {
+            let Some(path) = dialog.open_future(Some(&window)).await.ok().and_then(|file| file.path()) else {
+                return;
+            };
+            match import::import(&path) {
+                Ok(list) => {
+                    if let Some(policy) = list.policy {
+                        application.set_match_policy(policy);
+                    }
+                    application.set_words(list.words, path.parent().map(Path::to_owned));
+                },
+                Err(error) => application.show_message("Failed to import word list", &error.to_string()),
+            }
+        }));
+    }
+
+    pub fn show_export_dialog(&self) {
+        let window = self.active_window().unwrap();
+        let dialog = gtk::FileDialog::builder()
+            .title("Export word list")
+            .modal(true)
+            .initial_name("words.csv")
+            .filters(&word_list_filters())
+            .build();
+
+        MainContext::default().spawn_local(clone!(@weak self as application => async move {
+            let Some(path) = dialog.save_future(Some(&window)).await.ok().and_then(|file| file.path()) else {
+                return;
+            };
+            let result = export::export(&application.imp().trainer.borrow().resolved_words(), &path);
+            if let Err(error) = result {
+                application.show_message("Failed to export word list", &error.to_string());
+            }
+        }));
+    }
+
+    pub fn show_open_deck_dialog(&self) {
+        let window = self.active_window().unwrap();
+        let dialog = gtk::FileDialog::builder()
+            .title("Open deck")
+            .modal(true)
+            .filters(&deck_filters())
+            .build();
+
+        MainContext::default().spawn_local(clone!(@weak self as application => async move {
+            let Some(path) = dialog.open_future(Some(&window)).await.ok().and_then(|file| file.path()) else {
+                return;
+            };
+            if let Err(error) = application.open_deck(&path) {
+                application.show_message("Failed to open deck", &error.to_string());
+            }
+        }));
+    }
+
+    pub fn open_deck(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let bundle = Bundle::open(path)?;
+        // decks are unpacked by content, so decks with the same name do not overwrite each other's images
+        let folder = DECK_FOLDER.join(bundle.digest());
+        let words = bundle.unpack(&folder)?;
+        util::forget_folder(&folder.canonicalize()?);
+        self.set_match_policy(bundle.manifest.policy);
+        self.set_words(words, None);
+        Ok(())
+    }
+
+    pub fn show_save_deck_dialog(&self) {
+        let window = self.active_window().unwrap();
+        let dialog = gtk::FileDialog::builder()
+            .title("Save deck")
+            .modal(true)
+            .initial_name("words.deck")
+            .filters(&deck_filters())
+            .build();
+
+        MainContext::default().spawn_local(clone!(@weak self as application => async move {
+            let Some(path) = dialog.save_future(Some(&window)).await.ok().and_then(|file| file.path()) else {
+                return;
+            };
+            if let Err(error) = application.save_deck(&path).await {
+                application.show_message("Failed to save deck", &error.to_string());
+            }
+        }));
+    }
+
+    /// Downloads the images of all words and saves them together with the word list into a deck at `path`.
+    pub async fn save_deck(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let (words, sources, policy) = {
+            let trainer = self.imp().trainer.borrow();
+            let sources = trainer.words().iter().map(|word| trainer.image_source(word)).collect::<Vec<_>>();
+            (trainer.words().to_vec(), sources, trainer.match_policy().clone())
+        };
+        let name = path.file_stem().and_then(|name| name.to_str()).unwrap_or("deck");
+        let mut bundle = Bundle::new(name, "", policy);
+        for (word, source) in words.iter().zip(sources) {
+            let extension = source.extension()
+                .filter(|extension| is_valid_extension(extension))
+                .unwrap_or_else(|| "img".to_owned());
+            let data = util::load_data(source).await?;
+            bundle.add(word, &extension, data)?;
+        }
+        bundle.save(path)?;
+        Ok(())
+    }
+
+    /// Downloads the images of all words into the cache so they can be shown in offline mode.
+    pub fn download_images(&self) {
+        let (words, sources) = {
+            let trainer = self.imp().trainer.borrow();
+            let sources = trainer.words().iter().map(|word| trainer.image_source(word)).collect::<Vec<_>>();
+            (trainer.words().iter().map(|word| word.word.clone()).collect::<Vec<_>>(), sources)
+        };
+
+        MainContext::default().spawn_local(clone!(@weak self as application => async move {
+            let failures = words.iter()
+                .zip(image::download(sources).await)
+                .filter_map(|(word, result)| result.err().map(|error| format!("{word}: {error}")))
+                .collect::<Vec<_>>();
+            if failures.is_empty() {
+                application.show_message("Images downloaded", &format!("All {} images are available offline", words.len()));
+            } else {
+                application.show_message("Failed to download images", &failures.join("\n"));
+            }
+        }));
+    }
+
+    pub fn show_clear_cache_dialog(&self) {
+        let window = self.active_window();
+        let cache = image::cache();
+        let usage = cache.usage().unwrap_or_default();
+        let body = format!("{} images using {} are cached. Cleared images have to be downloaded again.", usage.entries, glib::format_size(usage.size));
+        let dialog = adw::MessageDialog::new(window.as_ref(), Some("Clear image cache?"), Some(&body));
+        dialog.add_responses(&[("cancel", "Cancel"), ("clear", "Clear")]);
+        dialog.set_response_appearance("clear", adw::ResponseAppearance::Destructive);
+        dialog.connect_response(Some("clear"), clone!(@weak self as application => move |_, _| {
+            if let Err(error) = cache.clear() {
+                application.show_message("Failed to clear image cache", &error.to_string());
+            }
+        }));
+        dialog.present();
+    }
+
+    pub fn show_message(&self, heading: &str, body: &str) {
+        let window = self.active_window();
+        let dialog = adw::MessageDialog::new(window.as_ref(), Some(heading), Some(body));
+        dialog.add_response("close", "Close");
+        dialog.present();
+    }
+
+    /// Replaces the word list. Relative image paths of the words are resolved against `directory`.
+    pub fn set_words(&self, words: Vec<Word>, directory: Option<PathBuf>) {
+        {
+            let mut trainer = self.imp().trainer.borrow_mut();
+            trainer.set_words(words);
+            trainer.set_directory(directory);
+            trainer.random();
+        }
+        self.emit_by_name::<()>("update-statistic", &[]);
+        if let Some(window) = self.windows().into_iter().find_map(|window| window.downcast::<Window>().ok()) {
+            window.refresh();
+        }
+    }
+
+    /// Changes how strictly guesses are compared and updates the menu to show it.
+    pub fn set_match_policy(&self, policy: MatchPolicy) {
+        self.imp().trainer.borrow_mut().set_match_policy(policy);
+        self.update_policy_actions();
+        self.schedule_autosave();
+    }
+
+    /// Changes one setting of the match policy.
+    fn change_match_policy(&self, change: impl FnOnce(&mut MatchPolicy)) {
+        let mut policy = self.imp().trainer.borrow().match_policy().clone();
+        change(&mut policy);
+        self.set_match_policy(policy);
+    }
+
+    fn update_policy_actions(&self) {
+        let policy = self.imp().trainer.borrow().match_policy().clone();
+        for (name, state) in [("case-sensitive", policy.case_sensitive), ("transliterate-umlauts", policy.transliterate_umlauts)] {
+            if let Some(action) = self.lookup_action(name).and_then(|action| action.downcast::<gio::SimpleAction>().ok()) {
+                action.set_state(&state.to_variant());
+            }
+        }
+    }
+
+    /// Saves the trainer once it stayed unchanged for [AUTOSAVE_DELAY]. Every call restarts the delay.
+    pub fn schedule_autosave(&self) {
+        if let Some(source) = self.imp().autosave_source.take() {
+            source.remove();
+        }
+        let source = glib::timeout_add_local_once(AUTOSAVE_DELAY, clone!(@weak self as application => move || {
+            application.imp().autosave_source.take();
+            application.autosave();
+        }));
+        self.imp().autosave_source.replace(Some(source));
+    }
+
+    /// Hands the trainer to the background writer if it changed since the last save.
+    fn autosave(&self) {
+        if let Some(autosave) = self.imp().autosave.borrow().as_ref() {
+            autosave.save(&mut self.imp().trainer.borrow_mut());
+        }
+    }
+
+    fn storage(&self) -> MutexGuard<'_, dyn Storage + 'static> {
+        self.imp().storage.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn profiles(&self) -> Vec<Profile> {
+        self.storage().profiles().unwrap_or_default()
+    }
+
+    /// Returns the profile whose progress is shown.
+    pub fn profile(&self) -> Profile {
+        let id = self.imp().profile.get();
+        self.profiles().into_iter()
+            .find(|profile| profile.id == id)
+            .unwrap_or_else(|| Profile { id, name: String::new() })
+    }
+
+    pub fn create_profile(&self, name: &str) -> Result<Profile, storage::Error> {
+        self.storage().create_profile(name)
+    }
+
+    pub fn rename_profile(&self, id: u32, name: &str) -> Result<(), storage::Error> {
+        self.storage().rename_profile(id, name)?;
+        self.update_title();
+        Ok(())
+    }
+
+    /// Deletes a profile with its progress. Deleting the active profile switches to another one.
+    pub fn delete_profile(&self, id: u32) -> Result<(), storage::Error> {
+        let active = self.imp().profile.get() == id;
+        if active {
+            // the progress is about to be deleted, so it must not be written again afterwards
+            self.stop_autosave();
+        }
+        let result = self.storage().delete_profile(id);
+        if active {
+            let next = self.storage().last_profile();
+            match next {
+                Ok(next) => self.load_profile(next.id),
+                Err(error) => self.show_message("Failed to switch profile", &error.to_string()),
+            }
+        }
+        result
+    }
+
+    /// Saves the progress of the active profile and continues with the one of `id`.
+    pub fn switch_profile(&self, id: u32) {
+        if id == self.imp().profile.get() || !self.profiles().iter().any(|profile| profile.id == id) {
+            return;
+        }
+        self.autosave();
+        self.stop_autosave();
+        self.show_save_errors();
+        self.load_profile(id);
+    }
+
+    fn load_profile(&self, id: u32) {
+        let imp = self.imp();
+        let (trainer, warnings) = storage::restore(&*self.storage(), id);
+        *imp.trainer.borrow_mut() = trainer;
+        self.update_policy_actions();
+        imp.autosave.replace(Some(autosave(imp.storage.clone(), id, imp.save_error_sender.clone())));
+        imp.profile.set(id);
+        let result = self.storage().set_last_profile(id);
+        if let Err(error) = result {
+            self.show_message("Failed to remember the profile", &error.to_string());
+        }
+        for (heading, body) in warnings {
+            self.show_message(&heading, &body);
+        }
+
+        self.emit_by_name::<()>("update-statistic", &[]);
+        self.update_title();
+        if let Some(window) = self.windows().into_iter().find_map(|window| window.downcast::<Window>().ok()) {
+            window.refresh();
+        }
+    }
+
+    /// Shows the name of the active profile in the title of the main window.
+    fn update_title(&self) {
+        let title = format!("Spelling Trainer – {}", self.profile().name);
+        if let Some(window) = self.windows().into_iter().find_map(|window| window.downcast::<Window>().ok()) {
+            window.set_title(Some(&title));
+        }
+    }
+
+    /// Waits for queued saves and stops saving the active profile.
+    fn stop_autosave(&self) {
+        if let Some(source) = self.imp().autosave_source.take() {
+            source.remove();
+        }
+        if let Some(autosave) = self.imp().autosave.take() {
+            autosave.finish();
+        }
+    }
+
+    /// Shows the errors of the background saves since they were last shown.
+    fn show_save_errors(&self) {
+        let errors = self.take_save_errors();
+        if !errors.is_empty() {
+            self.show_message("Failed to save progress", &errors.join("\n"));
+        }
+    }
+
+    /// Returns the errors of the background saves since they were last taken, without repetitions.
+    fn take_save_errors(&self) -> Vec<String> {
+        let mut errors = Vec::<String>::new();
+        for error in self.imp().save_errors.try_iter() {
+            if !errors.contains(&error) {
+                errors.push(error);
+            }
+        }
+        errors
+    }
+
     pub fn trainer(&self) -> Rc<RefCell<Trainer>> {
         self.imp().trainer.clone()
     }
@@ -103,11 +444,66 @@ impl Application {
                     application.show_about_dialog();
                 })
                 .build(),
+            gio::ActionEntry::builder("profiles")
+                .activate(move |application: &Application, _, _| {
+                    application.show_profiles_dialog();
+                })
+                .build(),
             gio::ActionEntry::builder("statistic")
                 .activate(move |application: &Application, _, _| {
                     application.show_statistic_dialog();
                 })
                 .build(),
+            gio::ActionEntry::builder("offline")
+                .state(false.to_variant())
+                .activate(move |_: &Application, action, _| {
+                    let offline = !action.state().and_then(|state| state.get::<bool>()).unwrap_or(false);
+                    action.set_state(&offline.to_variant());
+                    util::set_offline(offline);
+                })
+                .build(),
+            gio::ActionEntry::builder("case-sensitive")
+                .state(true.to_variant())
+                .activate(move |application: &Application, _, _| {
+                    application.change_match_policy(|policy| policy.case_sensitive = !policy.case_sensitive);
+                })
+                .build(),
+            gio::ActionEntry::builder("transliterate-umlauts")
+                .state(false.to_variant())
+                .activate(move |application: &Application, _, _| {
+                    application.change_match_policy(|policy| policy.transliterate_umlauts = !policy.transliterate_umlauts);
+                })
+                .build(),
+            gio::ActionEntry::builder("download-images")
+                .activate(move |application: &Application, _, _| {
+                    application.download_images();
+                })
+                .build(),
+            gio::ActionEntry::builder("clear-cache")
+                .activate(move |application: &Application, _, _| {
+                    application.show_clear_cache_dialog();
+                })
+                .build(),
+            gio::ActionEntry::builder("import")
+                .activate(move |application: &Application, _, _| {
+                    application.show_import_dialog();
+                })
+                .build(),
+            gio::ActionEntry::builder("export")
+                .activate(move |application: &Application, _, _| {
+                    application.show_export_dialog();
+                })
+                .build(),
+            gio::ActionEntry::builder("open-deck")
+                .activate(move |application: &Application, _, _| {
+                    application.show_open_deck_dialog();
+                })
+                .build(),
+            gio::ActionEntry::builder("save-deck")
+                .activate(move |application: &Application, _, _| {
+                    application.show_save_deck_dialog();
+                })
+                .build(),
             gio::ActionEntry::builder("reset")
                 .activate(move |application: &Application, _, _| {
                     application.imp().trainer.borrow_mut().reset_statistic();
@@ -115,8 +511,10 @@ impl Application {
                 })
                 .build(),
         ]);
+        self.update_policy_actions();
 
         self.connect_closure("update-statistic", false, closure_local!(move |application: Application| {
+            application.schedule_autosave();
             application.windows().iter()
                 .find(|window| window.is::<StatisticWindow>())
                 .map(|window| window.clone().downcast::<StatisticWindow>().unwrap().update_statistic());
@@ -124,18 +522,69 @@ impl Application {
     }
 }
 
+/// Saves to `profile` in the background. Failures are sent to `errors` and shown by the main loop.
+fn autosave(storage: SharedStorage, profile: u32, errors: Sender<String>) -> Autosave {
+    Autosave::new(storage, profile, move |error| {
+        // the receiver lives as long as the application
+        let _ = errors.send(error.to_string());
+        MainContext::default().invoke(|| {
+            if let Some(application) = gio::Application::default().and_then(|application| application.downcast::<Application>().ok()) {
+                application.show_save_errors();
+            }
+        });
+    })
+}
+
+fn word_list_filters() -> gio::ListStore {
+    let filter = gtk::FileFilter::new();
+    filter.set_name(Some("Word lists"));
+    filter.add_suffix("csv");
+    filter.add_suffix("json");
+    filter.add_suffix("toml");
+    let filters = gio::ListStore::new::<gtk::FileFilter>();
+    filters.append(&filter);
+    filters
+}
+
+fn deck_filters() -> gio::ListStore {
+    let filter = gtk::FileFilter::new();
+    filter.set_name(Some("Decks"));
+    filter.add_suffix("deck");
+    let filters = gio::ListStore::new::<gtk::FileFilter>();
+    filters.append(&filter);
+    filters
+}
+
 mod imp {
-    use std::cell::RefCell;
+    use std::cell::{Cell, RefCell};
     use std::rc::Rc;
+    use std::sync::mpsc::{self, Receiver, Sender};
     use adw::glib;
+    use adw::glib::SourceId;
     use adw::glib::subclass::Signal;
     use adw::subclass::prelude::*;
     use once_cell::sync::Lazy;
-    use super::{SAVE_FILE, SAVE_FOLDER};
-    use crate::model::Trainer;
+    use spelling_trainer_core::image;
+    use spelling_trainer_core::settings::{Settings, SETTINGS_FILE};
+    use spelling_trainer_core::storage::{self, Autosave, SessionLock, SharedStorage};
+    use spelling_trainer_core::Trainer;
 
     pub struct Application {
         pub trainer: Rc<RefCell<Trainer>>,
+        pub settings: Settings,
+        pub storage: SharedStorage,
+        /// The id of the profile the trainer belongs to.
+        pub profile: Cell<u32>,
+        /// Warnings to show as soon as there is a window.
+        pub warnings: RefCell<Vec<(String, String)>>,
+        pub autosave: RefCell<Option<Autosave>>,
+        /// The pending autosave, if the trainer changed recently.
+        pub autosave_source: RefCell<Option<SourceId>>,
+        /// Where the background saves report their failures.
+        pub save_error_sender: Sender<String>,
+        pub save_errors: Receiver<String>,
+        /// Keeps the terminal frontend from changing the progress at the same time, unless it was running first.
+        pub session: Option<SessionLock>,
     }
 
     #[glib::object_subclass]
@@ -145,15 +594,38 @@ mod imp {
         type ParentType = adw::Application;
 
         fn new() -> Self {
-            let trainer = if SAVE_FILE.exists() {
-                ciborium::from_reader(std::fs::File::open(SAVE_FILE.as_path()).expect("Failed to open save file")).expect("Failed to deserialize save file")
-            } else {
-                let mut trainer = Trainer::new(&super::WORDS[..]);
-                trainer.random();
-                trainer
+            let mut warnings = Vec::new();
+            let settings = Settings::load(&SETTINGS_FILE).unwrap_or_else(|error| {
+                warnings.push(("Failed to load settings".to_owned(), error.to_string()));
+                Settings::default()
+            });
+            let (storage, session) = storage::open(&settings.storage, &mut warnings);
+            let session = match session {
+                Ok(session) => Some(session),
+                Err(error) => {
+                    warnings.push(("Progress in use".to_owned(), format!("Changes made here or in the other Spelling Trainer may be lost: {error}")));
+                    None
+                }
+            };
+            let (profile, trainer) = {
+                let storage = storage.lock().expect("The storage is not shared yet");
+                let profile = storage.last_profile().map(|profile| profile.id).unwrap_or_default();
+                let (trainer, restore_warnings) = storage::restore(&*storage, profile);
+                warnings.extend(restore_warnings);
+                (profile, trainer)
             };
+            let (save_error_sender, save_errors) = mpsc::channel();
             Self {
                 trainer: Rc::new(RefCell::new(trainer)),
+                settings,
+                storage: storage.clone(),
+                profile: Cell::new(profile),
+                warnings: RefCell::new(warnings),
+                autosave: RefCell::new(Some(super::autosave(storage, profile, save_error_sender.clone()))),
+                autosave_source: RefCell::new(None),
+                save_error_sender,
+                save_errors,
+                session,
             }
         }
     }
@@ -173,6 +645,12 @@ mod imp {
     }
 
     impl ApplicationImpl for Application {
+        fn startup(&self) {
+            self.parent_startup();
+
+            self.obj().configure_network();
+        }
+
         fn activate(&self) {
             self.parent_activate();
 
@@ -182,11 +660,15 @@ mod imp {
         fn shutdown(&self) {
             self.parent_shutdown();
 
-            if !SAVE_FOLDER.exists() {
-                std::fs::create_dir_all(SAVE_FOLDER.as_path()).expect("Failed to create save folder");
+            self.obj().autosave();
+            self.obj().stop_autosave();
+            // the windows are gone, so the last failures can only be reported on the terminal
+            for error in self.obj().take_save_errors() {
+                eprintln!("Failed to save progress: {error}");
             }
 
-            ciborium::into_writer(&*self.trainer.borrow(), std::fs::File::create(SAVE_FILE.as_path()).expect("Failed to create save file")).expect("Failed to serialize save file");
+            // an untrimmed cache only costs disk space, so failing here is not worth aborting the shutdown
+            let _ = image::cache().evict(image::MAX_CACHE_SIZE);
         }
     }
     impl GtkApplicationImpl for Application {}
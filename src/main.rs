@@ -3,7 +3,6 @@ use adw::glib::ExitCode;
 use adw::prelude::*;
 use crate::application::Application;
 
-mod model;
 mod view;
 mod application;
 
@@ -1,3 +1,4 @@
 pub mod window;
 pub mod statistic;
-mod web_image;
+pub mod profiles;
+pub mod web_image;
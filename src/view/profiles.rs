@@ -0,0 +1,169 @@
+use adw::glib;
+use adw::glib::{clone, Cast, IsA, Object};
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use crate::application::Application;
+use spelling_trainer_core::profile::Profile;
+
+glib::wrapper! {
+    pub struct ProfileWindow(ObjectSubclass<imp::ProfileWindow>)
+        @extends adw::Window, gtk::Window, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native, gtk::Root,
+                    gtk::ShortcutManager;
+}
+
+impl ProfileWindow {
+    pub fn new(application: &impl IsA<gtk::Application>, parent: &impl IsA<gtk::Window>) -> Self {
+        let window = Object::builder::<ProfileWindow>()
+            .property("application", application)
+            .property("transient-for", parent)
+            .build();
+        window.imp().create_button.connect_clicked(clone!(@weak window => move |_| {
+            window.create_profile();
+        }));
+        window.imp().name_entry.connect_activate(clone!(@weak window => move |_| {
+            window.create_profile();
+        }));
+        window.update_profiles();
+        window
+    }
+
+    fn app(&self) -> Application {
+        self.application()
+            .expect("No application")
+            .downcast::<Application>()
+            .expect("Could not downcast to custom application")
+    }
+
+    /// Lists the profiles with the active one marked.
+    pub fn update_profiles(&self) {
+        let application = self.app();
+        let list = self.imp().profile_list.get();
+        list.remove_all();
+
+        let active = application.profile();
+        for profile in application.profiles() {
+            let row = adw::ActionRow::builder()
+                .title(glib::markup_escape_text(&profile.name))
+                .activatable(true)
+                .build();
+            if profile.id == active.id {
+                row.add_prefix(&gtk::Image::from_icon_name("object-select-symbolic"));
+            }
+
+            let rename = gtk::Button::builder()
+                .icon_name("document-edit-symbolic")
+                .tooltip_text("Rename")
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .build();
+            rename.connect_clicked(clone!(@weak self as window, @strong profile => move |_| {
+                window.show_rename_dialog(&profile);
+            }));
+            row.add_suffix(&rename);
+
+            let delete = gtk::Button::builder()
+                .icon_name("user-trash-symbolic")
+                .tooltip_text("Delete")
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .build();
+            delete.connect_clicked(clone!(@weak self as window, @strong profile => move |_| {
+                window.show_delete_dialog(&profile);
+            }));
+            row.add_suffix(&delete);
+
+            row.connect_activated(clone!(@weak self as window, @strong profile => move |_| {
+                window.app().switch_profile(profile.id);
+                window.close();
+            }));
+            list.append(&row);
+        }
+    }
+
+    fn create_profile(&self) {
+        let entry = self.imp().name_entry.get();
+        match self.app().create_profile(&entry.text()) {
+            Ok(_) => {
+                entry.set_text("");
+                self.update_profiles();
+            }
+            Err(error) => self.app().show_message("Failed to create profile", &error.to_string()),
+        }
+    }
+
+    fn show_rename_dialog(&self, profile: &Profile) {
+        let dialog = adw::MessageDialog::new(Some(self), Some("Rename profile"), None);
+        let entry = gtk::Entry::builder()
+            .text(profile.name.as_str())
+            .activates_default(true)
+            .build();
+        dialog.set_extra_child(Some(&entry));
+        dialog.add_responses(&[("cancel", "Cancel"), ("rename", "Rename")]);
+        dialog.set_response_appearance("rename", adw::ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("rename"));
+        dialog.connect_response(Some("rename"), clone!(@weak self as window, @strong profile => move |_, _| {
+            if let Err(error) = window.app().rename_profile(profile.id, &entry.text()) {
+                window.app().show_message("Failed to rename profile", &error.to_string());
+            }
+            window.update_profiles();
+        }));
+        dialog.present();
+    }
+
+    fn show_delete_dialog(&self, profile: &Profile) {
+        let body = format!("All progress of {} will be lost.", profile.name);
+        let dialog = adw::MessageDialog::new(Some(self), Some("Delete profile?"), Some(&body));
+        dialog.add_responses(&[("cancel", "Cancel"), ("delete", "Delete")]);
+        dialog.set_response_appearance("delete", adw::ResponseAppearance::Destructive);
+        dialog.connect_response(Some("delete"), clone!(@weak self as window, @strong profile => move |_, _| {
+            if let Err(error) = window.app().delete_profile(profile.id) {
+                window.app().show_message("Failed to delete profile", &error.to_string());
+            }
+            window.update_profiles();
+        }));
+        dialog.present();
+    }
+}
+
+mod imp {
+    use adw::gdk::{Key, ModifierType};
+    use adw::glib;
+    use adw::subclass::prelude::*;
+    use gtk::{CompositeTemplate, TemplateChild};
+
+    #[derive(CompositeTemplate, Default)]
+    #[template(resource = "/at/ac/tgm/pdamianik/spelling_trainer/profiles.ui")]
+    pub struct ProfileWindow {
+        #[template_child]
+        pub profile_list: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub name_entry: TemplateChild<gtk::Entry>,
+        #[template_child]
+        pub create_button: TemplateChild<gtk::Button>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ProfileWindow {
+        const NAME: &'static str = "ProfileWindow";
+        type Type = super::ProfileWindow;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.add_binding_action(Key::Escape, ModifierType::empty(), "window.close", None);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for ProfileWindow {}
+
+    impl WidgetImpl for ProfileWindow {}
+
+    impl WindowImpl for ProfileWindow {}
+
+    impl AdwWindowImpl for ProfileWindow {}
+}
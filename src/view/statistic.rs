@@ -1,7 +1,10 @@
-use adw::glib;
-use adw::glib::{Cast, IsA, Object};
+use std::cmp::Ordering;
+use adw::{gio, glib};
+use adw::glib::{BoxedAnyObject, Cast, IsA, Object};
 use adw::subclass::prelude::*;
+use chrono::Local;
 use gtk::prelude::*;
+use spelling_trainer_core::statistic::WordStatistic;
 
 glib::wrapper! {
     pub struct StatisticWindow(ObjectSubclass<imp::StatisticWindow>)
@@ -10,6 +13,11 @@ glib::wrapper! {
                     gtk::ShortcutManager;
 }
 
+struct WordRow {
+    word: String,
+    statistic: WordStatistic,
+}
+
 impl StatisticWindow {
     pub fn new(application: &impl IsA<gtk::Application>, parent: &impl IsA<gtk::Window>) -> Self {
         let window = Object::builder::<StatisticWindow>()
@@ -30,17 +38,81 @@ impl StatisticWindow {
 
         let correct = statistic.correct();
         let incorrect = statistic.incorrect();
+        let near_misses = statistic.near_misses();
+        let hinted = statistic.hinted();
+        let hints = statistic.hints();
         let total = statistic.total();
         let percent = correct as f64 / total as f64 * 100.0;
 
-        self.imp().statistic.set_text(&format!("{correct} correct, {incorrect} incorrect out of {total} ({percent:.2}%)"));
+        self.imp().statistic.set_text(&format!("{correct} correct ({hinted} with hints), {incorrect} incorrect ({near_misses} near misses) out of {total} ({percent:.2}%), {hints} hints used"));
         self.action_set_enabled("app.reset", total != 0);
+
+        let rows = trainer.borrow().word_statistics()
+            .map(|(word, statistic)| BoxedAnyObject::new(WordRow {
+                word: word.word.clone(),
+                statistic,
+            }))
+            .collect::<Vec<_>>();
+        let words = self.imp().words.get().expect("The word statistic model is not set up");
+        words.remove_all();
+        words.extend_from_slice(&rows);
+    }
+
+    fn setup_word_statistic(&self) {
+        let words = gio::ListStore::new::<BoxedAnyObject>();
+        let column_view = self.imp().word_statistic.get();
+
+        self.add_column("Word", |row| row.word.clone(), |a, b| a.word.cmp(&b.word));
+        self.add_column("Attempts", |row| row.statistic.attempts().to_string(), |a, b| a.statistic.attempts().cmp(&b.statistic.attempts()));
+        self.add_column("First try", |row| row.statistic.first_try().to_string(), |a, b| a.statistic.first_try().cmp(&b.statistic.first_try()));
+        self.add_column("Streak", |row| row.statistic.streak().to_string(), |a, b| a.statistic.streak().cmp(&b.statistic.streak()));
+        self.add_column("Hints", |row| row.statistic.hints().to_string(), |a, b| a.statistic.hints().cmp(&b.statistic.hints()));
+        self.add_column("Last seen", |row| row.statistic.last_seen()
+            .map(|last_seen| last_seen.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "never".to_owned()), |a, b| a.statistic.last_seen().cmp(&b.statistic.last_seen()));
+
+        let sorted = gtk::SortListModel::new(Some(words.clone()), column_view.sorter());
+        column_view.set_model(Some(&gtk::NoSelection::new(Some(sorted))));
+        self.imp().words.set(words).expect("The word statistic model is already set up");
+    }
+
+    fn add_column(
+        &self,
+        title: &str,
+        text: impl Fn(&WordRow) -> String + 'static,
+        compare: impl Fn(&WordRow, &WordRow) -> Ordering + 'static,
+    ) {
+        let factory = gtk::SignalListItemFactory::new();
+        factory.connect_setup(|_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().expect("The item is not a list item");
+            item.set_child(Some(&gtk::Label::builder().xalign(0.0).build()));
+        });
+        factory.connect_bind(move |_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().expect("The item is not a list item");
+            let row = item.item().and_downcast::<BoxedAnyObject>().expect("The item is not a word row");
+            let label = item.child().and_downcast::<gtk::Label>().expect("The child is not a label");
+            label.set_text(&text(&row.borrow::<WordRow>()));
+        });
+        let sorter = gtk::CustomSorter::new(move |a, b| {
+            let a = a.downcast_ref::<BoxedAnyObject>().expect("The item is not a word row");
+            let b = b.downcast_ref::<BoxedAnyObject>().expect("The item is not a word row");
+            let ordering = compare(&a.borrow::<WordRow>(), &b.borrow::<WordRow>());
+            ordering.into()
+        });
+        let column = gtk::ColumnViewColumn::builder()
+            .title(title)
+            .factory(&factory)
+            .sorter(&sorter)
+            .expand(true)
+            .build();
+        self.imp().word_statistic.append_column(&column);
     }
 }
 
 mod imp {
+    use std::cell::OnceCell;
     use adw::gdk::{Key, ModifierType};
-    use adw::glib;
+    use adw::{gio, glib};
     use adw::subclass::prelude::*;
     use gtk::{CompositeTemplate, TemplateChild};
 
@@ -49,6 +121,9 @@ mod imp {
     pub struct StatisticWindow {
         #[template_child]
         pub statistic: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub word_statistic: TemplateChild<gtk::ColumnView>,
+        pub words: OnceCell<gio::ListStore>,
     }
 
     #[glib::object_subclass]
@@ -67,11 +142,16 @@ mod imp {
         }
     }
 
-    impl ObjectImpl for StatisticWindow {}
+    impl ObjectImpl for StatisticWindow {
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.obj().setup_word_statistic();
+        }
+    }
 
     impl WidgetImpl for StatisticWindow {}
 
     impl WindowImpl for StatisticWindow {}
 
     impl AdwWindowImpl for StatisticWindow {}
-}
\ No newline at end of file
+}
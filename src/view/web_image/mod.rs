@@ -2,9 +2,11 @@ use gtk::glib;
 use gtk::glib::Object;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
-use reqwest::IntoUrl;
+use spelling_trainer_core::source::ImageSource;
 
-mod util;
+pub mod util;
+
+const DEFAULT_BOUNDS: util::Bounds = (800, 600);
 
 glib::wrapper! {
     pub struct WebImage(ObjectSubclass<imp::WebImage>)
@@ -23,19 +25,44 @@ impl WebImage {
         Self::default()
     }
 
-    pub async fn load(&self, url: impl IntoUrl + Send + 'static) -> Result<(), util::Error> {
-        let image = self.imp().image.clone();
-        let spinner = self.imp().spinner.clone();
-        image.borrow().set_visible(false);
-        spinner.borrow().set_visible(true);
-        spinner.borrow().start();
-        let image_data = util::load_image(url).await
-            .expect("Failed loading image data");
-        image.borrow().set_from_paintable(image_data.as_ref());
-        spinner.borrow().stop();
-        spinner.borrow().set_visible(false);
-        image.borrow().set_visible(true);
-        Ok(())
+    /// Loads the image from `source`. On failure the error is shown together with buttons to retry or skip the word.
+    pub async fn load(&self, source: ImageSource) -> Result<(), util::Error> {
+        let imp = self.imp();
+        imp.image.borrow().set_visible(false);
+        imp.error.borrow().set_visible(false);
+        imp.spinner.borrow().set_visible(true);
+        imp.spinner.borrow().start();
+        let result = util::load_image(source, self.bounds()).await;
+        imp.spinner.borrow().stop();
+        imp.spinner.borrow().set_visible(false);
+        match result {
+            Ok(texture) => {
+                imp.image.borrow().set_from_paintable(texture.as_ref());
+                imp.image.borrow().set_visible(true);
+                Ok(())
+            }
+            Err(error) => {
+                self.show_error(&error.to_string());
+                Err(error)
+            }
+        }
+    }
+
+    /// Returns the size in device pixels images are scaled down to, falling back to a default before the widget is shown.
+    pub fn bounds(&self) -> util::Bounds {
+        let (width, height) = (self.width(), self.height());
+        if width <= 0 || height <= 0 {
+            return DEFAULT_BOUNDS;
+        }
+        let scale = self.scale_factor();
+        (width * scale, height * scale)
+    }
+
+    pub fn show_error(&self, message: &str) {
+        let imp = self.imp();
+        imp.image.borrow().set_visible(false);
+        imp.message.borrow().set_text(message);
+        imp.error.borrow().set_visible(true);
     }
 }
 
@@ -43,14 +70,18 @@ mod imp {
     use std::cell::RefCell;
     use gtk::glib;
     use gtk::glib::subclass::prelude::*;
-    use gtk::{Image, Spinner};
+    use gtk::glib::subclass::Signal;
+    use gtk::{Align, Button, Image, Label, Orientation, Spinner};
     use gtk::prelude::*;
     use gtk::subclass::prelude::*;
+    use once_cell::sync::Lazy;
 
     #[derive(Default)]
     pub struct WebImage {
         pub image: RefCell<Image>,
         pub spinner: RefCell<Spinner>,
+        pub error: RefCell<gtk::Box>,
+        pub message: RefCell<Label>,
     }
 
     #[glib::object_subclass]
@@ -81,6 +112,44 @@ mod imp {
                 .build();
             self.spinner.replace(spinner.clone());
             obj.append(&spinner);
+
+            let message = Label::builder()
+                .wrap(true)
+                .justify(gtk::Justification::Center)
+                .build();
+            let retry = Button::with_label("Retry");
+            retry.connect_clicked(glib::clone!(@weak obj => move |_| obj.emit_by_name::<()>("retry", &[])));
+            let skip = Button::with_label("Skip word");
+            skip.connect_clicked(glib::clone!(@weak obj => move |_| obj.emit_by_name::<()>("skip", &[])));
+            let buttons = gtk::Box::builder()
+                .spacing(6)
+                .halign(Align::Center)
+                .build();
+            buttons.append(&retry);
+            buttons.append(&skip);
+            let error = gtk::Box::builder()
+                .orientation(Orientation::Vertical)
+                .spacing(12)
+                .valign(Align::Center)
+                .hexpand(true)
+                .visible(false)
+                .build();
+            error.append(&Image::from_icon_name("image-missing-symbolic"));
+            error.append(&message);
+            error.append(&buttons);
+            self.message.replace(message);
+            self.error.replace(error.clone());
+            obj.append(&error);
+        }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: Lazy<Vec<Signal>> = Lazy::new(|| {
+                vec![
+                    Signal::builder("retry").build(),
+                    Signal::builder("skip").build(),
+                ]
+            });
+            SIGNALS.as_ref()
         }
     }
 
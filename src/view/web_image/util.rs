@@ -1,47 +1,104 @@
-use std::path::PathBuf;
-use directories::ProjectDirs;
+use std::cell::RefCell;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use futures::StreamExt;
 use gtk::gdk::Texture;
 use gtk::gdk_pixbuf::PixbufLoader;
-use gtk::glib;
-use gtk::prelude::PixbufLoaderExt;
-use http_cache_reqwest::{CACacheManager, Cache, CacheMode, HttpCache, HttpCacheOptions};
-use lazy_static::lazy_static;
-use reqwest::{Client, IntoUrl};
-use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use gtk::{gio, glib};
+use gtk::prelude::{NetworkMonitorExt, PixbufLoaderExt};
+use spelling_trainer_core::image::{self, PREFETCH_CONCURRENCY};
+use spelling_trainer_core::image::cache::Lru;
+use spelling_trainer_core::source::ImageSource;
 use thiserror::Error;
-use tokio::spawn;
-
-lazy_static!(
-    static ref CACHE_FOLDER: PathBuf = ProjectDirs::from("at.ac", "tgm", "spelling_trainer").expect("Failed to get project dirs").cache_dir().to_owned();
-    static ref CLIENT: ClientWithMiddleware = ClientBuilder::new(Client::new())
-        .with(Cache(HttpCache {
-            mode: CacheMode::Default,
-            manager: CACacheManager {
-                path: CACHE_FOLDER.clone(),
-            },
-            options: HttpCacheOptions::default(),
-        }))
-        .build();
-);
+
+/// How many decoded textures are kept in memory.
+const TEXTURE_CACHE_CAPACITY: usize = 32;
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// Recently decoded textures together with the bounds they were scaled to. Textures can only be used on the main thread.
+    static TEXTURES: RefCell<Lru<ImageSource, (Bounds, Texture)>> = RefCell::new(Lru::new(TEXTURE_CACHE_CAPACITY));
+}
+
+/// The maximum width and height in pixels an image is scaled down to while decoding.
+pub type Bounds = (i32, i32);
 
 #[derive(Error, Debug)]
 pub enum Error {
     #[error(transparent)]
-    ReqwestError(#[from] reqwest::Error),
+    FetchError(#[from] image::Error),
     #[error(transparent)]
     PixBufError(#[from] glib::Error),
-    #[error(transparent)]
-    ReqwestMiddlewareError(#[from] reqwest_middleware::Error),
 }
 
-pub async fn load_image(url: impl IntoUrl + Send + 'static) -> Result<Option<Texture>, Error> {
-    let mut image_data = spawn(async {CLIENT.get(url).send().await}).await.unwrap()?.bytes_stream();
-    let image = PixbufLoader::new();
+/// Switches to only loading images from the cache.
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+/// Returns whether offline mode was switched on or no network is available.
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed) || !gio::NetworkMonitor::default().is_network_available()
+}
+
+pub async fn load_data(source: ImageSource) -> Result<Vec<u8>, image::Error> {
+    image::fetch(source, is_offline()).await
+}
+
+pub async fn load_image(source: ImageSource, bounds: Bounds) -> Result<Option<Texture>, Error> {
+    if let Some(texture) = cached(&source, bounds) {
+        return Ok(Some(texture));
+    }
+    let texture = decode(&load_data(source.clone()).await?, bounds)?;
+    if let Some(texture) = &texture {
+        TEXTURES.with(|textures| textures.borrow_mut().insert(source, (bounds, texture.clone())));
+    }
+    Ok(texture)
+}
+
+/// Drops the decoded textures of the image files in `folder`, so images written there again are decoded anew.
+pub fn forget_folder(folder: &Path) {
+    TEXTURES.with(|textures| {
+        textures.borrow_mut().retain(|source, _| !matches!(source, ImageSource::File(path) if path.starts_with(folder)))
+    });
+}
+
+/// Downloads and decodes the images of `sources` in the background, so loading them later is instant.
+pub async fn prefetch(sources: Vec<ImageSource>, bounds: Bounds) {
+    let pending = sources.into_iter()
+        .filter(|source| cached(source, bounds).is_none())
+        .collect::<Vec<_>>();
 
-    while let Some(chunk) = image_data.next().await {
-        image.write(&chunk?)?;
+    let mut loaded = futures::stream::iter(pending)
+        .map(|source| async move { (source.clone(), load_data(source).await) })
+        .buffer_unordered(PREFETCH_CONCURRENCY);
+    while let Some((source, data)) = loaded.next().await {
+        if let Ok(Some(texture)) = data.map_err(Error::from).and_then(|data| decode(&data, bounds)) {
+            TEXTURES.with(|textures| textures.borrow_mut().insert(source, (bounds, texture)));
+        }
     }
+}
+
+/// Returns the cached texture of `source` if it was decoded at least as large as `bounds`.
+fn cached(source: &ImageSource, bounds: Bounds) -> Option<Texture> {
+    TEXTURES.with(|textures| {
+        textures.borrow_mut().get(source)
+            .filter(|((width, height), _)| *width >= bounds.0 && *height >= bounds.1)
+            .map(|(_, texture)| texture.clone())
+    })
+}
+
+fn decode(data: &[u8], bounds: Bounds) -> Result<Option<Texture>, Error> {
+    let image = PixbufLoader::new();
+    image.connect_size_prepared(move |image, width, height| {
+        let scale = f64::min(bounds.0 as f64 / width as f64, bounds.1 as f64 / height as f64);
+        if scale < 1.0 {
+            image.set_size(((width as f64 * scale) as i32).max(1), ((height as f64 * scale) as i32).max(1));
+        }
+    });
+
+    image.write(data)?;
     image.close()?;
     let texture = image.pixbuf().map(|pixbuf| Texture::for_pixbuf(&pixbuf));
     Ok(texture)
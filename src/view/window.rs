@@ -2,10 +2,15 @@ use std::cell::RefCell;
 use std::rc::Rc;
 use glib::Object;
 use adw::{gio, glib};
-use adw::glib::{clone, IsA, MainContext};
+use adw::glib::{clone, closure_local, IsA, MainContext};
 use adw::subclass::prelude::*;
 use gtk::prelude::*;
-use crate::model::Trainer;
+use spelling_trainer_core::feedback::Letter;
+use spelling_trainer_core::{Trainer, BROKEN_IMAGE_FAILURES};
+use crate::view::web_image::{util, WebImage};
+
+/// How many images of the words that are likely to be asked next are loaded in the background.
+const PREFETCH_COUNT: usize = 3;
 
 glib::wrapper! {
     pub struct Window(ObjectSubclass<imp::Window>)
@@ -20,8 +25,15 @@ impl Window {
             .property("application", app)
             .build();
         window.action_set_enabled("win.check", false);
+        window.action_set_enabled("win.hint", false);
         window.load_image();
         window.imp().image_view.set_center_widget(Some(&window.imp().web_image));
+        window.imp().web_image.connect_closure("retry", false, closure_local!(@watch window => move |_: WebImage| {
+            window.load_image();
+        }));
+        window.imp().web_image.connect_closure("skip", false, closure_local!(@watch window => move |_: WebImage| {
+            window.skip();
+        }));
         window
     }
 
@@ -30,31 +42,120 @@ impl Window {
             .map(|app| app.downcast::<crate::application::Application>().unwrap().trainer())
     }
 
+    /// Resets the input and shows the selected word of the trainer.
+    pub fn refresh(&self) {
+        self.imp().check_button.set_label("Check");
+        self.imp().guess_entry.buffer().set_text("");
+        self.show_feedback(None);
+        self.show_hint(None);
+        self.load_image();
+    }
+
+    fn show_feedback(&self, diff: Option<&[Letter]>) {
+        let feedback = self.imp().feedback.get();
+        let Some(diff) = diff else {
+            feedback.set_visible(false);
+            return;
+        };
+
+        let markup = diff.iter()
+            .map(|letter| match *letter {
+                Letter::Same(c) => escape(c),
+                Letter::Missing(c) => format!("<span foreground=\"#26a269\" underline=\"single\">{}</span>", escape(c)),
+                Letter::Extra(c) => format!("<span foreground=\"#c01c28\" strikethrough=\"true\">{}</span>", escape(c)),
+                Letter::Replaced { expected, guessed } => format!(
+                    "<span foreground=\"#c01c28\" strikethrough=\"true\">{}</span><span foreground=\"#26a269\" underline=\"single\">{}</span>",
+                    escape(guessed), escape(expected),
+                ),
+                Letter::Swapped(first, second) => format!("<span foreground=\"#e66100\" underline=\"double\">{}{}</span>", escape(first), escape(second)),
+            })
+            .collect::<String>();
+        feedback.set_markup(&format!("Almost! {markup}"));
+        feedback.set_visible(true);
+    }
+
+    fn show_hint(&self, pattern: Option<&str>) {
+        let hint = self.imp().hint.get();
+        match pattern {
+            Some(pattern) => {
+                hint.set_text(pattern);
+                hint.set_visible(true);
+            }
+            None => hint.set_visible(false),
+        }
+    }
+
     fn load_image(&self) {
         let trainer = self.trainer().expect("The application does not have a trainer");
-        let word = trainer.borrow_mut().selected().cloned();
+        let selected = {
+            let trainer = trainer.borrow();
+            trainer.selected().map(|word| (word.word.clone(), trainer.image_source(word)))
+        };
 
-        if let Some(word) = word {
+        if let Some((word, source)) = selected {
             let main_context = MainContext::default();
             let image = self.imp().web_image.clone();
             main_context.spawn_local(clone!(@strong self as this => async move {
                 this.action_set_enabled("win.check", false);
-                image.load(word.url.clone()).await
-                    .expect("Failed loading image");
-                this.action_set_enabled("win.check", true);
+                this.action_set_enabled("win.hint", false);
+                match image.load(source.clone()).await {
+                    Ok(()) => {
+                        trainer.borrow_mut().record_image_loaded(&word);
+                        this.changed();
+                        this.action_set_enabled("win.check", true);
+                        this.action_set_enabled("win.hint", true);
+                        let upcoming = {
+                            let trainer = trainer.borrow();
+                            trainer.upcoming(PREFETCH_COUNT).into_iter()
+                                .map(|word| trainer.image_source(word))
+                                .collect()
+                        };
+                        util::prefetch(upcoming, image.bounds()).await;
+                    }
+                    Err(error) => {
+                        let failures = trainer.borrow_mut().record_image_failure(&word)
+                            .filter(|failures| *failures >= BROKEN_IMAGE_FAILURES);
+                        this.changed();
+                        if let Some(failures) = failures {
+                            image.show_error(&format!("{error}\n\nThis image failed to load {failures} times in a row, please check {source}"));
+                        }
+                    }
+                }
             }));
         } else {
             self.action_set_enabled("win.check", false);
+            self.action_set_enabled("win.hint", false);
+        }
+    }
+
+    fn skip(&self) {
+        let trainer = self.trainer().expect("The application does not have a trainer");
+        trainer.borrow_mut().skip();
+        self.changed();
+        self.refresh();
+    }
+
+    /// Lets the application know that the trainer changed, so it is saved.
+    fn changed(&self) {
+        // the image may finish loading after the window was closed
+        if let Some(application) = self.application() {
+            application.emit_by_name::<()>("update-statistic", &[]);
         }
     }
 }
 
+fn escape(c: char) -> String {
+    glib::markup_escape_text(&c.to_string()).to_string()
+}
+
 mod imp {
     use adw::glib::{self};
     use adw::subclass::prelude::*;
     use glib::subclass::InitializingObject;
-    use gtk::{Button, CenterBox, CompositeTemplate, Entry};
+    use gtk::{Button, CenterBox, CompositeTemplate, Entry, Label};
     use gtk::prelude::*;
+    use spelling_trainer_core::feedback::GuessOutcome;
+    use spelling_trainer_core::hint::Hint;
     use crate::view::web_image::WebImage;
 
     #[derive(CompositeTemplate, Default)]
@@ -63,8 +164,12 @@ mod imp {
         #[template_child]
         pub image_view: TemplateChild<CenterBox>,
         #[template_child]
+        pub hint: TemplateChild<Label>,
+        #[template_child]
         pub guess_entry: TemplateChild<Entry>,
         #[template_child]
+        pub feedback: TemplateChild<Label>,
+        #[template_child]
         pub check_button: TemplateChild<Button>,
         pub web_image: WebImage,
     }
@@ -81,18 +186,38 @@ mod imp {
                 let text = entry.buffer().text();
                 let check_button = window.imp().check_button.get();
                 let trainer = window.trainer().expect("The application does not have a trainer");
-                let correct = trainer.borrow_mut().guess(&text);
+                let outcome = trainer.borrow_mut().guess(&text);
                 window.application().unwrap().emit_by_name::<()>("update-statistic", &[]);
-                if correct {
-                    check_button.set_label("Check");
-                    entry.buffer().set_text("");
-                    trainer.borrow_mut().random();
-                    window.load_image();
-                } else {
-                    check_button.set_label("Try again");
+                match outcome {
+                    GuessOutcome::Correct => {
+                        trainer.borrow_mut().random();
+                        window.refresh();
+                    }
+                    GuessOutcome::NearMiss { diff, .. } => {
+                        check_button.set_label("Try again");
+                        window.show_feedback(Some(&diff));
+                    }
+                    GuessOutcome::Wrong => {
+                        check_button.set_label("Try again");
+                        window.show_feedback(None);
+                    }
                 }
             });
 
+            klass.install_action("win.hint", Some("s"), |window, _, parameter| {
+                let hint = match parameter.and_then(|parameter| parameter.str()) {
+                    Some("letter-count") => Hint::LetterCount,
+                    Some("first-letter") => Hint::FirstLetter,
+                    Some("last-letter") => Hint::LastLetter,
+                    Some("next-letter") => Hint::NextLetter,
+                    _ => return,
+                };
+                let trainer = window.trainer().expect("The application does not have a trainer");
+                let pattern = trainer.borrow_mut().hint(hint);
+                window.show_hint(pattern.as_deref());
+                window.application().unwrap().emit_by_name::<()>("update-statistic", &[]);
+            });
+
             klass.bind_template();
         }
 
@@ -0,0 +1,388 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use image::DynamicImage;
+use spelling_trainer_core::feedback::{GuessOutcome, Letter};
+use spelling_trainer_core::hint::Hint;
+use spelling_trainer_core::profile::Profile;
+use spelling_trainer_core::source::ImageSource;
+use spelling_trainer_core::storage::{Autosave, SharedStorage};
+use spelling_trainer_core::{Trainer, BROKEN_IMAGE_FAILURES};
+use thiserror::Error;
+use tokio::runtime::Handle;
+use crate::picture::{Picture, Rendering};
+
+/// The hints behind the function keys F1 to F4.
+pub const HINTS: [Hint; 4] = [Hint::LetterCount, Hint::FirstLetter, Hint::LastLetter, Hint::NextLetter];
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    FetchError(#[from] spelling_trainer_core::image::Error),
+    #[error(transparent)]
+    DecodeError(#[from] image::ImageError),
+}
+
+/// How the session is run, as given on the command line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Options {
+    /// The name of the profile to practice with instead of the last one.
+    pub profile: Option<String>,
+    /// Only show pictures that were downloaded before.
+    pub offline: bool,
+    pub rendering: Rendering,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            profile: None,
+            offline: false,
+            rendering: Rendering::Blocks,
+        }
+    }
+}
+
+/// The result of the last action of the learner.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Status {
+    Asking,
+    /// The previous word was spelled correctly.
+    Correct(String),
+    Almost(Vec<Letter>),
+    Wrong,
+    /// The previous word was skipped.
+    Skipped(String),
+}
+
+pub enum PictureState {
+    /// There is no word to show a picture of.
+    Missing,
+    Loading,
+    Loaded(Picture),
+    Failed(String),
+}
+
+/// A picture that finished loading in the background.
+struct Loaded {
+    word: String,
+    result: Result<DynamicImage, Error>,
+}
+
+/// A practice session in the terminal.
+pub struct Application {
+    pub trainer: Trainer,
+    pub profile: Profile,
+    pub options: Options,
+    /// What the learner typed so far.
+    pub input: String,
+    pub status: Status,
+    /// The selected word with the hidden letters replaced by underscores, once a hint was used.
+    pub hint: Option<String>,
+    pub picture: PictureState,
+    /// Where the picture of the selected word comes from.
+    pub source: Option<ImageSource>,
+    /// Warnings to show until the next guess.
+    pub warnings: Vec<(String, String)>,
+    pub quit: bool,
+    autosave: Option<Autosave>,
+    /// Where the autosave reports failed saves, which cannot be printed while the terminal is drawn on.
+    save_errors: Receiver<String>,
+    runtime: Handle,
+    sender: Sender<Loaded>,
+    receiver: Receiver<Loaded>,
+}
+
+impl Application {
+    /// Starts practicing with `trainer`, which belongs to `profile` in `storage`. Pictures are loaded on `runtime`.
+    pub fn new(trainer: Trainer, storage: SharedStorage, profile: Profile, options: Options, runtime: Handle) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let (save_error_sender, save_errors) = mpsc::channel();
+        // the receiver is gone once the session ended, then the error is not shown anymore
+        let autosave = Autosave::new(storage, profile.id, move |error| {
+            let _ = save_error_sender.send(error.to_string());
+        });
+        let mut application = Self {
+            trainer,
+            profile,
+            options,
+            input: String::new(),
+            status: Status::Asking,
+            hint: None,
+            picture: PictureState::Missing,
+            source: None,
+            warnings: Vec::new(),
+            quit: false,
+            autosave: Some(autosave),
+            save_errors,
+            runtime,
+            sender,
+            receiver,
+        };
+        if application.trainer.selected().is_none() {
+            application.trainer.random();
+        }
+        application.load_picture();
+        application
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        // some terminals report releasing keys as well
+        if key.kind == KeyEventKind::Release {
+            return;
+        }
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => self.quit = true,
+            KeyCode::Char('c') if control => self.quit = true,
+            KeyCode::Char('r') if control => self.load_picture(),
+            KeyCode::Enter => self.check(),
+            KeyCode::Tab => self.skip(),
+            KeyCode::F(n @ 1..=4) => self.use_hint(HINTS[n as usize - 1]),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Char(c) if !control && !key.modifiers.contains(KeyModifiers::ALT) => self.input.push(c),
+            _ => {}
+        }
+    }
+
+    /// Takes over the pictures that finished loading and shows failed saves.
+    pub fn update(&mut self) {
+        while let Ok(error) = self.save_errors.try_recv() {
+            self.warnings.push(("Failed to save progress".to_owned(), error));
+        }
+        while let Ok(loaded) = self.receiver.try_recv() {
+            // the learner moved on while the picture was loading
+            if self.trainer.selected().map(|word| word.word.as_str()) != Some(loaded.word.as_str()) {
+                continue;
+            }
+            match loaded.result {
+                Ok(image) => {
+                    self.trainer.record_image_loaded(&loaded.word);
+                    self.picture = PictureState::Loaded(Picture::new(image));
+                }
+                Err(error) => {
+                    let failures = self.trainer.record_image_failure(&loaded.word)
+                        .filter(|failures| *failures >= BROKEN_IMAGE_FAILURES);
+                    let message = match (failures, &self.source) {
+                        (Some(failures), Some(source)) => format!("{error}\n\nThis image failed to load {failures} times in a row, please check {source}"),
+                        _ => error.to_string(),
+                    };
+                    self.picture = PictureState::Failed(message);
+                }
+            }
+            self.save();
+        }
+    }
+
+    /// Queues the progress to be saved if it changed.
+    pub fn save(&mut self) {
+        if let Some(autosave) = &self.autosave {
+            autosave.save(&mut self.trainer);
+        }
+    }
+
+    /// Saves the progress and waits until it is written.
+    pub fn finish(mut self) {
+        self.save();
+        if let Some(autosave) = self.autosave.take() {
+            autosave.finish();
+        }
+    }
+
+    fn check(&mut self) {
+        if self.input.trim().is_empty() {
+            return;
+        }
+        self.warnings.clear();
+        let word = self.trainer.selected().map(|word| word.word.clone());
+        match self.trainer.guess(&self.input) {
+            GuessOutcome::Correct => {
+                self.trainer.random();
+                self.status = Status::Correct(word.unwrap_or_default());
+                self.next();
+            }
+            GuessOutcome::NearMiss { diff, .. } => self.status = Status::Almost(diff),
+            GuessOutcome::Wrong => self.status = Status::Wrong,
+        }
+        self.save();
+    }
+
+    fn skip(&mut self) {
+        let word = self.trainer.selected().map(|word| word.word.clone());
+        self.trainer.skip();
+        self.status = word.map_or(Status::Asking, Status::Skipped);
+        self.next();
+        self.save();
+    }
+
+    fn use_hint(&mut self, hint: Hint) {
+        if let Some(pattern) = self.trainer.hint(hint) {
+            self.hint = Some(pattern);
+            self.save();
+        }
+    }
+
+    /// Resets the input and shows the newly selected word.
+    fn next(&mut self) {
+        self.input.clear();
+        self.hint = None;
+        self.load_picture();
+    }
+
+    fn load_picture(&mut self) {
+        let Some(word) = self.trainer.selected() else {
+            self.source = None;
+            self.picture = PictureState::Missing;
+            return;
+        };
+        let source = self.trainer.image_source(word);
+        let word = word.word.clone();
+        self.source = Some(source.clone());
+        self.picture = PictureState::Loading;
+
+        let sender = self.sender.clone();
+        let offline = self.options.offline;
+        self.runtime.spawn(async move {
+            let result = match spelling_trainer_core::image::fetch(source, offline).await {
+                Ok(data) => image::load_from_memory(&data).map_err(Error::from),
+                Err(error) => Err(error.into()),
+            };
+            // the receiver is gone once the session ended, then the picture is not needed anymore
+            let _ = sender.send(Loaded { word, result });
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use spelling_trainer_core::save::Restored;
+    use spelling_trainer_core::storage::Storage;
+    use spelling_trainer_core::storage::file::FileStorage;
+    use spelling_trainer_core::Word;
+    use tokio::runtime::Runtime;
+    use super::*;
+
+    fn word(spelling: &str) -> Word {
+        Word::new(spelling, ImageSource::File(format!("/does/not/exist/{spelling}.png").into()))
+    }
+
+    fn press(application: &mut Application, keys: &str) {
+        for c in keys.chars() {
+            application.handle_key(KeyEvent::from(KeyCode::Char(c)));
+        }
+    }
+
+    fn session(runtime: &Runtime, dir: &tempfile::TempDir) -> (SharedStorage, Application) {
+        let storage = FileStorage::open(dir.path()).expect("readable storage");
+        let profile = storage.last_profile().expect("default profile");
+        let storage: SharedStorage = Arc::new(Mutex::new(storage));
+        let trainer = Trainer::new(vec![word("dog")]);
+        let application = Application::new(trainer, storage.clone(), profile, Options::default(), runtime.handle().clone());
+        (storage, application)
+    }
+
+    #[test]
+    fn type_and_correct_input() {
+        let runtime = Runtime::new().expect("runtime");
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let (_, mut application) = session(&runtime, &dir);
+
+        press(&mut application, "dox");
+        application.handle_key(KeyEvent::from(KeyCode::Backspace));
+
+        assert_eq!(application.input, "do", "expect the last letter to be removed");
+    }
+
+    #[test]
+    fn correct_guess_is_saved() {
+        let runtime = Runtime::new().expect("runtime");
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let (storage, mut application) = session(&runtime, &dir);
+        let profile = application.profile.id;
+
+        press(&mut application, "dog");
+        application.handle_key(KeyEvent::from(KeyCode::Enter));
+
+        assert_eq!(application.status, Status::Correct("dog".to_owned()), "expect the guess to be correct");
+        assert!(application.input.is_empty(), "expect the input to be cleared for the next word");
+        application.finish();
+        let restored = storage.lock().expect("unpoisoned storage").restore(profile);
+        assert!(matches!(restored, Restored::Save(trainer) if trainer.statistic().correct() == 1), "expect the progress to be saved");
+    }
+
+    #[test]
+    fn near_miss_shows_difference() {
+        let runtime = Runtime::new().expect("runtime");
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let (_, mut application) = session(&runtime, &dir);
+
+        press(&mut application, "dgo");
+        application.handle_key(KeyEvent::from(KeyCode::Enter));
+
+        assert_eq!(application.status, Status::Almost(vec![Letter::Same('d'), Letter::Swapped('o', 'g')]), "expect the swapped letters to be shown");
+        assert_eq!(application.input, "dgo", "expect the input to be kept for correcting it");
+    }
+
+    #[test]
+    fn hint_with_function_key() {
+        let runtime = Runtime::new().expect("runtime");
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let (_, mut application) = session(&runtime, &dir);
+
+        application.handle_key(KeyEvent::from(KeyCode::F(2)));
+
+        assert_eq!(application.hint.as_deref(), Some("d _ _"), "expect the first letter to be revealed");
+        assert_eq!(application.trainer.statistic().hints(), 1, "expect the hint to be counted");
+    }
+
+    #[test]
+    fn missing_picture_is_reported() {
+        let runtime = Runtime::new().expect("runtime");
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let (_, mut application) = session(&runtime, &dir);
+
+        for _ in 0..100 {
+            application.update();
+            if !matches!(application.picture, PictureState::Loading) {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        assert!(matches!(application.picture, PictureState::Failed(_)), "expect the missing file to be reported");
+        assert_eq!(application.trainer.image_failures("dog"), 1, "expect the failure to be counted");
+    }
+
+    #[test]
+    fn failed_save_is_shown() {
+        let runtime = Runtime::new().expect("runtime");
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let (storage, mut application) = session(&runtime, &dir);
+        storage.lock().expect("unpoisoned storage").delete_profile(application.profile.id).expect("deleted profile");
+
+        application.handle_key(KeyEvent::from(KeyCode::F(1)));
+        for _ in 0..100 {
+            application.update();
+            if !application.warnings.is_empty() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        assert_eq!(application.warnings.first().map(|(heading, _)| heading.as_str()), Some("Failed to save progress"), "expect the failed save to be shown");
+    }
+
+    #[test]
+    fn quit_with_escape() {
+        let runtime = Runtime::new().expect("runtime");
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let (_, mut application) = session(&runtime, &dir);
+
+        application.handle_key(KeyEvent::from(KeyCode::Esc));
+
+        assert!(application.quit, "expect the session to end");
+    }
+}
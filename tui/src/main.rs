@@ -0,0 +1,193 @@
+use std::error::Error;
+use std::io::{stdout, Stdout};
+use std::process::ExitCode;
+use std::time::Duration;
+use crossterm::event::{self, Event};
+use crossterm::execute;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::Terminal;
+use ratatui::backend::CrosstermBackend;
+use spelling_trainer_core::image;
+use spelling_trainer_core::profile::Profile;
+use spelling_trainer_core::settings::{Settings, SETTINGS_FILE};
+use spelling_trainer_core::storage::{self, Storage};
+use tokio::runtime::Runtime;
+use crate::application::{Application, Options};
+use crate::picture::Rendering;
+
+mod application;
+mod picture;
+mod view;
+
+const USAGE: &str = "\
+Usage: spelling_trainer_tui [OPTIONS]
+
+Practices spelling in the terminal with the same profiles and progress as the Spelling Trainer app.
+
+Options:
+  --profile <NAME>  Practice with the profile NAME instead of the last one
+  --offline         Only show pictures that were downloaded before
+  --ascii           Draw pictures as ASCII art instead of colored blocks
+  --help            Show this help";
+
+/// How often the picture is checked for while waiting for keys.
+const TICK: Duration = Duration::from_millis(100);
+
+fn main() -> ExitCode {
+    let options = match parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("{message}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match run(options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Reads the command line arguments. Returns nothing if the help was asked for.
+fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut options = Options::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--profile" => options.profile = Some(args.next().ok_or("--profile needs the name of a profile")?),
+            "--offline" => options.offline = true,
+            "--ascii" => options.rendering = Rendering::Ascii,
+            "--help" | "-h" => return Ok(None),
+            other => return Err(format!("unknown argument {other}")),
+        }
+    }
+    Ok(Some(options))
+}
+
+fn run(options: Options) -> Result<(), Box<dyn Error>> {
+    let mut warnings = Vec::new();
+    let settings = Settings::load(&SETTINGS_FILE).unwrap_or_else(|error| {
+        warnings.push(("Failed to load settings".to_owned(), error.to_string()));
+        Settings::default()
+    });
+    if let Err(error) = image::configure(settings.network.clone()) {
+        warnings.push(("Failed to load settings".to_owned(), error.to_string()));
+    }
+    let (storage, session) = storage::open(&settings.storage, &mut warnings);
+    // two sessions saving the same progress would undo each other's changes
+    let _session = session?;
+    let (profile, trainer) = {
+        let mut storage = storage.lock().expect("The storage is not shared yet");
+        let profile = choose_profile(&mut *storage, options.profile.as_deref())?;
+        let (trainer, restore_warnings) = storage::restore(&*storage, profile.id);
+        warnings.extend(restore_warnings);
+        (profile, trainer)
+    };
+
+    let runtime = Runtime::new()?;
+    let mut application = Application::new(trainer, storage, profile, options, runtime.handle().clone());
+    application.warnings = warnings;
+
+    let mut terminal = enter()?;
+    let result = practice(&mut terminal, &mut application);
+    let left = leave(&mut terminal);
+    // the progress is saved even if the terminal could not be restored
+    application.finish();
+    // an untrimmed cache only costs disk space
+    let _ = image::cache().evict(image::MAX_CACHE_SIZE);
+    result?;
+    Ok(left?)
+}
+
+/// Returns the profile called `name`, or the one that was used last.
+fn choose_profile(storage: &mut dyn Storage, name: Option<&str>) -> Result<Profile, Box<dyn Error>> {
+    let Some(name) = name else {
+        return Ok(storage.last_profile()?);
+    };
+    let profiles = storage.profiles()?;
+    let Some(profile) = profiles.iter().find(|profile| profile.name.to_lowercase() == name.to_lowercase()) else {
+        let names = profiles.iter().map(|profile| profile.name.as_str()).collect::<Vec<_>>();
+        return Err(format!("there is no profile called {name}, the profiles are: {}", names.join(", ")).into());
+    };
+    storage.set_last_profile(profile.id)?;
+    Ok(profile.clone())
+}
+
+fn practice(terminal: &mut Terminal<CrosstermBackend<Stdout>>, application: &mut Application) -> std::io::Result<()> {
+    while !application.quit {
+        application.update();
+        terminal.draw(|frame| view::draw(frame, application))?;
+        if event::poll(TICK)? {
+            if let Event::Key(key) = event::read()? {
+                application.handle_key(key);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn enter() -> std::io::Result<Terminal<CrosstermBackend<Stdout>>> {
+    enable_raw_mode()?;
+    execute!(stdout(), EnterAlternateScreen)?;
+    // leave the terminal usable if drawing panics
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = disable_raw_mode();
+        let _ = execute!(stdout(), LeaveAlternateScreen);
+        hook(info);
+    }));
+    Terminal::new(CrosstermBackend::new(stdout()))
+}
+
+fn leave(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> std::io::Result<()> {
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>().into_iter()
+    }
+
+    #[test]
+    fn parse_options() {
+        let options = parse(args(&["--profile", "Anna", "--offline", "--ascii"])).expect("valid arguments");
+
+        assert_eq!(options, Some(Options {
+            profile: Some("Anna".to_owned()),
+            offline: true,
+            rendering: Rendering::Ascii,
+        }), "expect every option to be read");
+    }
+
+    #[test]
+    fn parse_help() {
+        assert_eq!(parse(args(&["--offline", "--help"])), Ok(None), "expect the help to be asked for");
+    }
+
+    #[test]
+    fn parse_invalid_arguments() {
+        assert!(parse(args(&["--profile"])).is_err(), "expect the missing name to be rejected");
+        assert!(parse(args(&["--colour"])).is_err(), "expect unknown arguments to be rejected");
+    }
+
+    #[test]
+    fn choose_profile_by_name() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let mut storage = storage::file::FileStorage::open(dir.path()).expect("readable storage");
+        let anna = storage.create_profile("Anna").expect("new profile");
+
+        assert_eq!(choose_profile(&mut storage, Some("anna")).expect("existing profile"), anna, "expect the name to match in any case");
+        assert_eq!(storage.last_profile().expect("last profile"), anna, "expect the profile to be remembered");
+        assert!(choose_profile(&mut storage, Some("Ben")).is_err(), "expect an unknown profile to be rejected");
+    }
+}
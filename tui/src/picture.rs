@@ -0,0 +1,164 @@
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, Rgba};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+
+/// The characters used for ASCII art, from the darkest to the brightest pixels.
+const RAMP: &[u8] = b"@%#*+=-:. ";
+
+/// Pixels that are more transparent than this show the terminal background.
+const OPAQUE: u8 = 128;
+
+/// How a picture is drawn in the terminal.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Rendering {
+    /// Two pixels per cell in true color, drawn with half blocks.
+    Blocks,
+    /// One character per cell that is the brighter the brighter the pixel is.
+    Ascii,
+}
+
+/// A decoded picture together with the lines it was last drawn as.
+pub struct Picture {
+    image: DynamicImage,
+    rendered: Option<((Rendering, u16, u16), Vec<Line<'static>>)>,
+}
+
+impl Picture {
+    pub fn new(image: DynamicImage) -> Self {
+        Self {
+            image,
+            rendered: None,
+        }
+    }
+
+    /// Returns the picture scaled to fit into `width` times `height` cells. Drawing it the same way again is free.
+    pub fn lines(&mut self, rendering: Rendering, width: u16, height: u16) -> &[Line<'static>] {
+        let key = (rendering, width, height);
+        if !matches!(&self.rendered, Some((other, _)) if *other == key) {
+            let lines = match rendering {
+                Rendering::Blocks => blocks(&self.image, width, height),
+                Rendering::Ascii => ascii(&self.image, width, height),
+            };
+            self.rendered = Some((key, lines));
+        }
+        self.rendered.as_ref().map(|(_, lines)| lines.as_slice()).unwrap_or_default()
+    }
+}
+
+/// Draws `image` with the upper half block, whose foreground is the upper and background the lower pixel.
+pub fn blocks(image: &DynamicImage, width: u16, height: u16) -> Vec<Line<'static>> {
+    if width == 0 || height == 0 {
+        return Vec::new();
+    }
+    // half blocks make the pixels about square
+    let image = image.resize(width as u32, height as u32 * 2, FilterType::Triangle).to_rgba8();
+    (0..image.height()).step_by(2)
+        .map(|y| {
+            (0..image.width())
+                .map(|x| {
+                    let upper = color(*image.get_pixel(x, y));
+                    let lower = if y + 1 < image.height() { color(*image.get_pixel(x, y + 1)) } else { Color::Reset };
+                    Span::styled("▀", Style::default().fg(upper).bg(lower))
+                })
+                .collect::<Vec<_>>()
+                .into()
+        })
+        .collect()
+}
+
+/// Draws `image` with characters of different brightness, for terminals without colors.
+pub fn ascii(image: &DynamicImage, width: u16, height: u16) -> Vec<Line<'static>> {
+    let (image_width, image_height) = image.dimensions();
+    if width == 0 || height == 0 || image_width == 0 || image_height == 0 {
+        return Vec::new();
+    }
+    // cells are about twice as high as wide
+    let scale = f64::min(width as f64 / image_width as f64, height as f64 * 2.0 / image_height as f64);
+    let columns = ((image_width as f64 * scale) as u32).clamp(1, width as u32);
+    let rows = ((image_height as f64 * scale / 2.0) as u32).clamp(1, height as u32);
+    let image = image.resize_exact(columns, rows, FilterType::Triangle).to_rgba8();
+    image.rows()
+        .map(|row| row.map(|pixel| character(*pixel)).collect::<String>().into())
+        .collect()
+}
+
+fn color(Rgba([red, green, blue, alpha]): Rgba<u8>) -> Color {
+    if alpha < OPAQUE {
+        Color::Reset
+    } else {
+        Color::Rgb(red, green, blue)
+    }
+}
+
+fn character(Rgba([red, green, blue, alpha]): Rgba<u8>) -> char {
+    if alpha < OPAQUE {
+        return ' ';
+    }
+    let luminance = 0.2126 * red as f64 + 0.7152 * green as f64 + 0.0722 * blue as f64;
+    let idx = (luminance / 256.0 * RAMP.len() as f64) as usize;
+    RAMP[idx.min(RAMP.len() - 1)] as char
+}
+
+#[cfg(test)]
+mod test {
+    use image::RgbaImage;
+    use super::*;
+
+    /// An image that is black on the left and white on the right.
+    fn halves(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, _| {
+            if x < width / 2 { Rgba([0, 0, 0, 255]) } else { Rgba([255, 255, 255, 255]) }
+        }))
+    }
+
+    #[test]
+    fn ascii_keeps_aspect_ratio() {
+        let lines = ascii(&halves(40, 20), 80, 40);
+
+        assert_eq!(lines.len(), 20, "expect the picture to be limited by the width");
+        assert!(lines.iter().all(|line| line.width() == 80), "expect every row to fill the width");
+    }
+
+    #[test]
+    fn ascii_brightness() {
+        let lines = ascii(&halves(4, 2), 4, 1);
+
+        assert_eq!(lines, vec![Line::from("@@  ")], "expect dark pixels to be dense and bright ones to be empty");
+    }
+
+    #[test]
+    fn blocks_use_two_pixels_per_cell() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_fn(1, 2, |_, y| {
+            if y == 0 { Rgba([255, 0, 0, 255]) } else { Rgba([0, 0, 255, 255]) }
+        }));
+
+        let lines = blocks(&image, 1, 1);
+
+        assert_eq!(lines, vec![Line::from(Span::styled("▀", Style::default().fg(Color::Rgb(255, 0, 0)).bg(Color::Rgb(0, 0, 255))))], "expect the upper pixel in the foreground and the lower one in the background");
+    }
+
+    #[test]
+    fn transparent_pixels_show_background() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 2, Rgba([255, 255, 255, 0])));
+
+        assert_eq!(ascii(&image, 1, 1), vec![Line::from(" ")], "expect a transparent pixel to be empty");
+        assert_eq!(blocks(&image, 1, 1)[0].spans[0].style.fg, Some(Color::Reset), "expect a transparent pixel to have no color");
+    }
+
+    #[test]
+    fn nothing_to_draw_on() {
+        assert!(blocks(&halves(4, 4), 0, 10).is_empty(), "expect no lines without width");
+        assert!(ascii(&halves(4, 4), 10, 0).is_empty(), "expect no lines without height");
+    }
+
+    #[test]
+    fn rendered_once_per_size() {
+        let mut picture = Picture::new(halves(4, 2));
+
+        let first = picture.lines(Rendering::Ascii, 4, 1).to_vec();
+
+        assert_eq!(picture.lines(Rendering::Ascii, 4, 1), first.as_slice(), "expect the same lines for the same size");
+        assert_ne!(picture.lines(Rendering::Ascii, 2, 1), first.as_slice(), "expect other lines for another size");
+    }
+}
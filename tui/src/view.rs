@@ -0,0 +1,104 @@
+use ratatui::Frame;
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
+use ratatui::widgets::block::Title;
+use spelling_trainer_core::feedback::Letter;
+use crate::application::{Application, PictureState, Status};
+
+const GREEN: Color = Color::Rgb(0x26, 0xa2, 0x69);
+const RED: Color = Color::Rgb(0xc0, 0x1c, 0x28);
+const ORANGE: Color = Color::Rgb(0xe6, 0x61, 0x00);
+
+const KEYS: &str = "Enter check · Tab skip · F1 letter count · F2 first letter · F3 last letter · F4 next letter · Ctrl+R reload picture · Esc quit";
+
+/// Draws the picture of the selected word with the input below it.
+pub fn draw(frame: &mut Frame, application: &mut Application) {
+    let [picture, source, status, input, keys] = *Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(3),
+            Constraint::Length(1),
+            Constraint::Length(2),
+            Constraint::Length(3),
+            Constraint::Length(1),
+        ])
+        .split(frame.size()) else {
+        return;
+    };
+
+    draw_picture(frame, application, picture);
+
+    let source_line = application.source.as_ref()
+        .map(|source| format!("Picture: {source}"))
+        .unwrap_or_default();
+    frame.render_widget(Paragraph::new(source_line).style(Style::default().add_modifier(Modifier::DIM)), source);
+
+    frame.render_widget(Paragraph::new(status_text(application)).wrap(Wrap { trim: true }), status);
+
+    let input_block = Block::default()
+        .borders(Borders::ALL)
+        .title(application.hint.as_deref().map_or_else(|| "Spelling".to_owned(), |hint| format!("Spelling ({hint})")));
+    let inner = input_block.inner(input);
+    frame.render_widget(Paragraph::new(application.input.as_str()).block(input_block), input);
+    let cursor = application.input.chars().count().min(inner.width.saturating_sub(1) as usize) as u16;
+    frame.set_cursor(inner.x + cursor, inner.y);
+
+    frame.render_widget(Paragraph::new(KEYS).style(Style::default().add_modifier(Modifier::DIM)), keys);
+}
+
+fn draw_picture(frame: &mut Frame, application: &mut Application, area: Rect) {
+    let statistic = application.trainer.statistic();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Spelling Trainer – {}", application.profile.name))
+        .title(Title::from(format!("correct {} · incorrect {} · near misses {}", statistic.correct(), statistic.incorrect(), statistic.near_misses()))
+            .alignment(Alignment::Right));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let rendering = application.options.rendering;
+    let paragraph = match &mut application.picture {
+        PictureState::Missing => Paragraph::new("There are no words to practice. Import a word list in the Spelling Trainer app."),
+        PictureState::Loading => Paragraph::new("Loading picture…"),
+        PictureState::Loaded(picture) => Paragraph::new(picture.lines(rendering, inner.width, inner.height).to_vec()),
+        PictureState::Failed(message) => Paragraph::new(format!("{message}\n\nPress Ctrl+R to retry or Tab to skip the word."))
+            .style(Style::default().fg(RED))
+            .wrap(Wrap { trim: true }),
+    };
+    frame.render_widget(paragraph.alignment(Alignment::Center), inner);
+}
+
+fn status_text(application: &Application) -> Text<'static> {
+    if !application.warnings.is_empty() {
+        return application.warnings.iter()
+            .map(|(heading, body)| Line::styled(format!("{heading}: {body}"), Style::default().fg(ORANGE)))
+            .collect::<Vec<_>>()
+            .into();
+    }
+    match &application.status {
+        Status::Asking => Line::from("What is in the picture?").into(),
+        Status::Correct(word) => Line::styled(format!("Correct, it was {word}!"), Style::default().fg(GREEN)).into(),
+        Status::Almost(diff) => {
+            let mut spans = vec![Span::raw("Almost! ")];
+            spans.extend(diff.iter().flat_map(letter));
+            Line::from(spans).into()
+        }
+        Status::Wrong => Line::styled("Try again", Style::default().fg(RED)).into(),
+        Status::Skipped(word) => Line::from(format!("Skipped {word}")).into(),
+    }
+}
+
+/// Marks missing letters like the app does: green and underlined, extra ones red and crossed out.
+fn letter(letter: &Letter) -> Vec<Span<'static>> {
+    let missing = Style::default().fg(GREEN).add_modifier(Modifier::UNDERLINED);
+    let extra = Style::default().fg(RED).add_modifier(Modifier::CROSSED_OUT);
+    match *letter {
+        Letter::Same(c) => vec![Span::raw(c.to_string())],
+        Letter::Missing(c) => vec![Span::styled(c.to_string(), missing)],
+        Letter::Extra(c) => vec![Span::styled(c.to_string(), extra)],
+        Letter::Replaced { expected, guessed } => vec![Span::styled(guessed.to_string(), extra), Span::styled(expected.to_string(), missing)],
+        Letter::Swapped(first, second) => vec![Span::styled(format!("{first}{second}"), Style::default().fg(ORANGE).add_modifier(Modifier::UNDERLINED))],
+    }
+}